@@ -4,11 +4,12 @@ extern crate test;
 extern crate icepick;
 use self::test::Bencher;
 use icepick::score;
+use icepick::score::Folding;
 
 fn score(choice: &str, query: &str) -> f32 {
     let choice_stirng = choice.to_string();
     let query_stirng = query.to_string();
-    match score::score(&choice_stirng,  &query_stirng) {
+    match score::score(&choice_stirng, &query_stirng, Folding::default()) {
         Some(ref matching) => matching.quality.to_f32(),
         None => 0.0,
     }
@@ -4,7 +4,11 @@ extern crate test;
 extern crate icepick;
 use self::test::Bencher;
 use icepick::search::Search;
-use icepick::sorted_result_set::SortedResultSet;
+use icepick::sorted_result_set::{SortedResultSet, Rank};
+use icepick::scorer::{Scorer, Fuzzy};
+use icepick::score::Folding;
+use icepick::index;
+use icepick::choices::Choices;
 
 fn one_two_three() -> Vec<String> {
     vec!["one".to_string(),
@@ -12,31 +16,75 @@ fn one_two_three() -> Vec<String> {
          "three".to_string()]
 }
 
-fn input_times(n: usize) ->Vec<String> {
-    let mut result: Vec<String> = Vec::new();
-    for thing in one_two_three().iter().cycle().take(n) {
-        result.push(thing.clone());
-    }
+fn input_times(n: usize) -> Choices {
+    let mut result = Choices::new();
+    result.extend(one_two_three().iter().cycle().take(n));
     result
 }
 
+fn corpus() -> Vec<String> {
+    include_str!("30000.txt").lines().map(|line| line.to_string()).collect()
+}
+
 #[bench]
 fn filter_speed(b: &mut Bencher) {
     let initial_elements = input_times(1000);
-    let query = "t";
-    let mut f = Vec::new();
-    for g in initial_elements.iter() {
-        f.push(g);
-    }
+    let mut scorer = Fuzzy::default();
+    scorer.prepare("t", Folding::default());
+    let f = index::candidates(&initial_elements);
 
 
     b.iter(||{
         let mut results = SortedResultSet::new(20);
-        Search::iter_matches(query, &f,
-                                |matching| {
-                                    let quality = matching.quality.to_f32();
-                                    results.push(matching, quality)
+        Search::iter_matches(&scorer, &initial_elements, &f,
+                                |candidate, matching| {
+                                    let rank = Rank::new(&matching, candidate as usize, &[]);
+                                    results.push(matching, rank)
                                 });
         results
     });
 }
+
+#[bench]
+fn filter_corpus(b: &mut Bencher) {
+    let lines = Choices::from(corpus());
+    let candidates = index::candidates(&lines);
+    let mut scorer = Fuzzy::default();
+    scorer.prepare("qzx", Folding::default());
+
+    b.iter(|| {
+        let mut count = 0;
+        Search::iter_matches(&scorer, &lines, &candidates, |_, _| count += 1);
+        count
+    });
+}
+
+#[bench]
+fn filter_corpus_scoring_every_choice(b: &mut Bencher) {
+    let lines = corpus();
+    let mut scorer = Fuzzy::default();
+    scorer.prepare("qzx", Folding::default());
+
+    b.iter(|| {
+        lines.iter().filter(|line| scorer.score(line).is_some()).count()
+    });
+}
+
+#[bench]
+fn index_corpus(b: &mut Bencher) {
+    let lines = corpus();
+
+    b.iter(|| Choices::from(&lines).len());
+}
+
+#[bench]
+fn search_corpus_for_a_common_query(b: &mut Bencher) {
+    let mut search = Some(Search::blank(corpus(), None, 20));
+
+    b.iter(|| {
+        let next = search.take().unwrap().with_query("e");
+        let count = next.num_matches();
+        search = Some(next);
+        count
+    });
+}
@@ -10,7 +10,7 @@ impl <'a> Ansi<'a> {
         self.io.write(out.as_ref());
     }
 
-    fn esc(input: &str) -> String {
+    pub fn esc(input: &str) -> String {
         format!("\x1b[{}", input)
     }
 
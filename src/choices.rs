@@ -0,0 +1,205 @@
+use index::Signature;
+use memmap::Mmap;
+
+use std::fs::File;
+use std::io;
+use std::ops::{Index, Range};
+use std::str;
+use std::sync::Arc;
+
+const MAX_CHUNK: usize = ::std::u32::MAX as usize;
+
+// Every choice, owned and only ever appended to. Choices are stored back to back
+// in chunks of text that clones share, so a clone is cheap to make and to send to
+// another thread, while the original keeps growing. Besides its text, a choice
+// costs a 4 byte offset and its signature.
+#[derive(Clone, Debug, Default)]
+pub struct Choices {
+    chunks: Vec<Arc<Chunk>>,
+    starts: Vec<usize>,
+    len: usize,
+}
+
+// Up to 4GB of text, either read from the input or mapped from a file. `ends[i]` is
+// where the i-th choice of the chunk ends, counted from `base`.
+#[derive(Debug)]
+struct Chunk {
+    text: Text,
+    base: usize,
+    ends: Vec<u32>,
+    signatures: Vec<Signature>,
+}
+
+#[derive(Debug)]
+enum Text {
+    Owned(String),
+    // Checked to be UTF-8 once, when mapped.
+    Mapped(Arc<Mmap>),
+}
+
+impl Chunk {
+    fn owned() -> Chunk {
+        Chunk { text: Text::Owned(String::new()), base: 0, ends: Vec::new(), signatures: Vec::new() }
+    }
+
+    fn mapped(map: &Arc<Mmap>, base: usize) -> Chunk {
+        Chunk { text: Text::Mapped(map.clone()), base: base, ends: Vec::new(), signatures: Vec::new() }
+    }
+
+    fn fits(&self, length: usize) -> bool {
+        match self.text {
+            Text::Owned(ref text) => text.len() + length <= MAX_CHUNK,
+            Text::Mapped(_) => false,
+        }
+    }
+
+    fn push(&mut self, choice: &str) {
+        if let Text::Owned(ref mut text) = self.text {
+            text.push_str(choice);
+            self.ends.push(text.len() as u32);
+            self.signatures.push(Signature::of(choice));
+        }
+    }
+
+    // Mapped lines still have their line break and surrounding whitespace, which reading stdin trims.
+    fn get(&self, local: usize) -> &str {
+        let start = self.base + if local == 0 { 0 } else { self.ends[local - 1] as usize };
+        let end = self.base + self.ends[local] as usize;
+        match self.text {
+            Text::Owned(ref text) => &text[start..end],
+            Text::Mapped(ref map) => unsafe { str::from_utf8_unchecked(&map[start..end]) }.trim(),
+        }
+    }
+}
+
+impl Choices {
+    pub fn new() -> Choices {
+        Choices::default()
+    }
+
+    // Uses the lines of a file without copying them. The file must not change while it is mapped.
+    pub fn map(file: &File) -> io::Result<Choices> {
+        let mut choices = Choices::new();
+        if file.metadata()?.len() == 0 { return Ok(choices) }
+
+        let map = Arc::new(unsafe { Mmap::map(file)? });
+        let text = str::from_utf8(&map[..]).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let mut chunk = Chunk::mapped(&map, 0);
+        let mut start = 0;
+        let breaks = text.match_indices('\n').map(|(offset, _)| offset + 1);
+        let last = if text.ends_with('\n') { None } else { Some(text.len()) };
+        for end in breaks.chain(last) {
+            if end - chunk.base > MAX_CHUNK {
+                choices.push_chunk(chunk);
+                chunk = Chunk::mapped(&map, start);
+            }
+            chunk.ends.push((end - chunk.base) as u32);
+            chunk.signatures.push(Signature::of(text[start..end].trim()));
+            start = end;
+        }
+        choices.push_chunk(chunk);
+        Ok(choices)
+    }
+
+    fn push_chunk(&mut self, chunk: Chunk) {
+        let added = chunk.ends.len();
+        if added == 0 { return }
+
+        self.chunks.push(Arc::new(chunk));
+        self.starts.push(self.len);
+        self.len += added;
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Returns the indices of the new choices.
+    pub fn extend<I, S>(&mut self, choices: I) -> Range<usize> where I: IntoIterator<Item=S>, S: AsRef<str> {
+        let first = self.len;
+        for choice in choices.into_iter() {
+            let choice = choice.as_ref();
+            self.writable_chunk(choice.len()).push(choice);
+            self.len += 1;
+        }
+        first..self.len
+    }
+
+    // The last chunk if no clone shares it and the choice fits, a new one otherwise.
+    fn writable_chunk(&mut self, length: usize) -> &mut Chunk {
+        let fits = match self.chunks.last_mut().and_then(Arc::get_mut) {
+            Some(chunk) => chunk.fits(length),
+            None => false,
+        };
+        if !fits {
+            self.chunks.push(Arc::new(Chunk::owned()));
+            self.starts.push(self.len);
+        }
+        Arc::get_mut(self.chunks.last_mut().unwrap()).unwrap()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.entry(index).map(|(choice, _)| choice)
+    }
+
+    // A choice together with its signature.
+    pub fn entry(&self, index: usize) -> Option<(&str, Signature)> {
+        if index >= self.len { return None }
+
+        let chunk = match self.starts.binary_search(&index) {
+            Ok(chunk) => chunk,
+            Err(next) => next - 1,
+        };
+        let local = index - self.starts[chunk];
+        let chunk = &self.chunks[chunk];
+        Some((chunk.get(local), chunk.signatures[local]))
+    }
+
+    pub fn iter(&self) -> Iter {
+        Iter { choices: self, index: 0 }
+    }
+}
+
+impl Index<usize> for Choices {
+    type Output = str;
+
+    fn index(&self, index: usize) -> &str {
+        self.get(index).expect("no choice at this index")
+    }
+}
+
+pub struct Iter<'a> {
+    choices: &'a Choices,
+    index: usize,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let choice = self.choices.get(self.index);
+        self.index += 1;
+        choice
+    }
+}
+
+impl From<Vec<String>> for Choices {
+    fn from(choices: Vec<String>) -> Choices {
+        let mut store = Choices::new();
+        store.extend(choices);
+        store
+    }
+}
+
+impl<'a> From<&'a Vec<String>> for Choices {
+    fn from(choices: &'a Vec<String>) -> Choices {
+        let mut store = Choices::new();
+        store.extend(choices);
+        store
+    }
+}
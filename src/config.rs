@@ -0,0 +1,21 @@
+use score::{Case, Scheme};
+use search::Tolerance;
+use sorted_result_set::Tiebreak;
+use marks::MarkOrder;
+
+// Everything that can be configured from the command line.
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    pub initial_query: Option<String>,
+    pub case: Case,
+    pub ignore_diacritics: bool,
+    pub scheme: Scheme,
+    pub tolerance: Tolerance,
+    pub tiebreaks: Vec<Tiebreak>,
+    pub explain: bool,
+    pub file: Option<String>,
+    pub multi: bool,
+    pub max_marks: Option<usize>,
+    pub mark_order: MarkOrder,
+    pub vi: bool,
+}
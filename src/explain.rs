@@ -0,0 +1,122 @@
+use score::{Match, Bonus, SCORE_GAP_LEADING, SCORE_GAP_INNER, SCORE_GAP_TRAILING};
+use sorted_result_set::{Rank, Tiebreak};
+use std::fmt;
+
+// How a match was ranked, to answer why one result shows up above another.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Explanation {
+    pub choice: String,
+    pub index: usize,
+    pub positions: Vec<usize>,
+    pub window: usize,
+    pub components: Vec<Component>,
+    pub quality: f32,
+    pub tiebreaks: Vec<(Tiebreak, usize)>,
+}
+
+// A bonus or penalty, with how many characters earned it and what it added up to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Component {
+    pub name: &'static str,
+    pub count: usize,
+    pub score: f32,
+}
+
+impl Explanation {
+    // `components` are what the scorer broke the quality down into, see `Scorer::explain`.
+    // Components of the same name are added up, and whatever they leave unexplained is
+    // reported as `other`. An exact match is worth more than any bonus, so it is not
+    // broken down at all.
+    pub fn new(matching: &Match, components: Vec<Component>, index: usize, tiebreaks: &[Tiebreak]) -> Explanation {
+        let quality = matching.quality.to_f32();
+        let mut merged = Vec::new();
+        if quality.is_finite() {
+            for component in components.into_iter() {
+                add(&mut merged, component.name, component.count, component.score);
+            }
+            let explained = merged.iter().fold(0.0, |sum, component| sum + component.score);
+            if (quality - explained).abs() > 0.0001 {
+                merged.push(Component { name: "other", count: 0, score: quality - explained });
+            }
+        }
+
+        let keys = Rank::new(matching, index, tiebreaks).keys;
+        let window = match (matching.positions.first(), matching.positions.last()) {
+            (Some(first), Some(last)) => last - first + 1,
+            _ => 0,
+        };
+
+        Explanation {
+            choice: matching.original.to_string(),
+            index: index,
+            positions: matching.positions.clone(),
+            window: window,
+            components: merged,
+            quality: quality,
+            tiebreaks: tiebreaks.iter().cloned().zip(keys.into_iter()).collect(),
+        }
+    }
+}
+
+// Breaks the quality of characters matched at `positions` down the way `score::fuzzy` computes it.
+pub fn fuzzy(choice: &str, positions: &[usize]) -> Vec<Component> {
+    let mut components: Vec<Component> = Vec::new();
+    if positions.is_empty() { return components }
+
+    let chars: Vec<char> = choice.chars().collect();
+    for (n, &position) in positions.iter().enumerate() {
+        let bonus = if n > 0 && positions[n - 1] + 1 == position {
+            Some(Bonus::Consecutive)
+        } else {
+            let last = if position == 0 { '/' } else { chars[position - 1] };
+            Bonus::between(last, chars[position])
+        };
+        if let Some(bonus) = bonus {
+            add(&mut components, bonus.name(), 1, bonus.score());
+        }
+    }
+
+    let first = positions[0];
+    let last = positions[positions.len() - 1];
+    let inner = last - first + 1 - positions.len();
+    let trailing = chars.len() - last - 1;
+    add(&mut components, "leading gap", first, first as f32 * SCORE_GAP_LEADING);
+    add(&mut components, "inner gap", inner, inner as f32 * SCORE_GAP_INNER);
+    add(&mut components, "trailing gap", trailing, trailing as f32 * SCORE_GAP_TRAILING);
+    components
+}
+
+fn add(components: &mut Vec<Component>, name: &'static str, count: usize, score: f32) {
+    if count == 0 { return }
+
+    match components.iter().position(|component| component.name == name) {
+        Some(idx) => {
+            components[idx].count += count;
+            components[idx].score += score;
+        },
+        None => components.push(Component { name: name, count: count, score: score }),
+    }
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let positions: Vec<String> = self.positions.iter().map(|position| position.to_string()).collect();
+
+        writeln!(f, "#{} {}", self.index, self.choice)?;
+        writeln!(f, "  {:<16}{}", "positions", positions.join(", "))?;
+        writeln!(f, "  {:<16}{}", "window", self.window)?;
+        for component in self.components.iter() {
+            let count = if component.count == 0 { "".to_string() } else { format!("x{}", component.count) };
+            writeln!(f, "  {:<16}{:<6}{:+.3}", component.name, count, component.score)?;
+        }
+        if self.quality.is_finite() {
+            writeln!(f, "  {:<16}{:<6}{:.3}", "quality", "", self.quality)?;
+        } else {
+            writeln!(f, "  {:<16}{}", "quality", "exact match")?;
+        }
+        for &(tiebreak, key) in self.tiebreaks.iter() {
+            writeln!(f, "  {:<16}{}", format!("{} tiebreak", tiebreak.name()), key)?;
+        }
+        Ok(())
+    }
+}
@@ -45,4 +45,8 @@ impl IO for FakeIO {
 
    fn reset(&self) {
    }
+
+   fn wait(&mut self, _milliseconds: i32) -> bool {
+       !self.input.is_empty()
+   }
 }
@@ -0,0 +1,191 @@
+use scorer::Scorer;
+use search::{Search, Hit, Tolerance};
+use sorted_result_set::{SortedResultSet, Rank, Tiebreak};
+use index::{Candidate, Candidates};
+use choices::Choices;
+
+use rayon::prelude::*;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Sender, Receiver};
+use std::thread;
+
+const SHARD_SIZE: usize = 4096;
+
+// Scores candidates with a prepared scorer, giving up once `cancelled` is set.
+pub struct Scoring<'a> {
+    pub scorer: &'a dyn Scorer,
+    pub choices: &'a Choices,
+    pub tiebreaks: &'a [Tiebreak],
+    pub limit: usize,
+    pub cancelled: &'a AtomicBool,
+}
+
+impl<'a> Scoring<'a> {
+    // Scores the candidates, falling back to all choices matched with typos when none match as typed.
+    // Also tells whether choices with typos were let in.
+    pub fn filter(&self, candidates: &Candidates, all: &Candidates, tolerance: Tolerance) -> Option<(SortedResultSet<Hit>, Vec<Candidate>, bool)> {
+        let with_typos = tolerance == Tolerance::Always;
+        let (result, filtered_choices) = self.shards(candidates, with_typos)?;
+        if filtered_choices.is_empty() && tolerance == Tolerance::Fallback {
+            let (result, filtered_choices) = self.shards(all, true)?;
+            Some((result, filtered_choices, true))
+        } else {
+            Some((result, filtered_choices, with_typos))
+        }
+    }
+
+    // Scores shards of the candidates in parallel, each into results of its own that are merged
+    // afterwards. Every match is kept in input order, so the number of matches stays exact.
+    pub fn shards(&self, candidates: &Candidates, with_typos: bool) -> Option<(SortedResultSet<Hit>, Vec<Candidate>)> {
+        let chunks: Vec<&[Candidate]> = candidates.parts().iter().flat_map(|part| part.chunks(SHARD_SIZE)).collect();
+        let shards: Vec<(SortedResultSet<Hit>, Vec<Candidate>)> = chunks.par_iter().map(|shard| {
+            let mut result = SortedResultSet::new(self.limit);
+            let mut filtered_choices = Vec::new();
+            if self.is_cancelled() {
+                // The results are thrown away anyway.
+            } else if with_typos {
+                Search::iter_typo_matches(self.scorer, self.choices, shard, |candidate, matching| {
+                    let rank = Rank::new(&matching, candidate as usize, self.tiebreaks);
+                    filtered_choices.push(candidate);
+                    result.push(Hit::new(candidate as usize, matching), rank);
+                });
+            } else {
+                self.collect_matches(shard, &mut result, &mut filtered_choices);
+            }
+            (result, filtered_choices)
+        }).collect();
+        if self.is_cancelled() { return None }
+
+        let (results, filtered_choices): (Vec<_>, Vec<Vec<Candidate>>) = shards.into_iter().unzip();
+        Some((SortedResultSet::merge_all(self.limit, results), filtered_choices.concat()))
+    }
+
+    // Once the results are full, a choice that cannot beat the worst of them is only
+    // checked for a match, so it is still counted without being scored.
+    fn collect_matches(&self, candidates: &[Candidate], result: &mut SortedResultSet<Hit>, filtered_choices: &mut Vec<Candidate>) {
+        let scorer = self.scorer;
+        let required = scorer.signature();
+        for &candidate in candidates.iter() {
+            let (choice, signature) = self.choices.entry(candidate as usize).unwrap();
+            if !signature.contains(required) { continue }
+
+            let needed = if result.is_full() { result.worst().map(|worst| worst.quality) } else { None };
+            if needed.map_or(false, |needed| scorer.upper_bound(choice) < needed) {
+                if scorer.matches(choice) { filtered_choices.push(candidate) }
+                continue;
+            }
+
+            if let Some(matching) = scorer.score(choice) {
+                let rank = Rank::new(&matching, candidate as usize, self.tiebreaks);
+                filtered_choices.push(candidate);
+                result.push(Hit::new(candidate as usize, matching), rank);
+            }
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+// Everything filtering for one query needs, owned so that it can run on another thread.
+// `cached` tells whether the candidates are the cached matches of the query itself,
+// with or without typos, so they only need to be ranked.
+pub struct Filter {
+    pub query: String,
+    pub epoch: usize,
+    pub scorer: Box<dyn Scorer>,
+    pub choices: Choices,
+    pub candidates: Candidates,
+    pub all: Candidates,
+    pub cached: Option<bool>,
+    pub tiebreaks: Vec<Tiebreak>,
+    pub limit: usize,
+    pub tolerance: Tolerance,
+}
+
+// The best results for a query and all of its matches.
+// `covered` is how many choices there were when filtering started.
+pub struct Filtered {
+    pub query: String,
+    pub epoch: usize,
+    pub covered: usize,
+    pub result: SortedResultSet<Hit>,
+    pub matched: Candidates,
+    pub with_typos: bool,
+}
+
+impl Filter {
+    pub fn run(&self, cancelled: &AtomicBool) -> Option<Filtered> {
+        let scoring = Scoring {
+            scorer: &*self.scorer,
+            choices: &self.choices,
+            tiebreaks: &self.tiebreaks,
+            limit: self.limit,
+            cancelled: cancelled,
+        };
+
+        let (result, matched, with_typos) = match self.cached {
+            _ if self.query.is_empty() => (SortedResultSet::new(self.limit), self.all.clone(), false),
+            Some(with_typos) => (scoring.shards(&self.candidates, with_typos)?.0, self.candidates.clone(), with_typos),
+            None => {
+                let (result, matched, with_typos) = scoring.filter(&self.candidates, &self.all, self.tolerance)?;
+                (result, Candidates::from(matched), with_typos)
+            },
+        };
+
+        Some(Filtered {
+            query: self.query.clone(),
+            epoch: self.epoch,
+            covered: self.choices.len(),
+            result: result,
+            matched: matched,
+            with_typos: with_typos,
+        })
+    }
+}
+
+// Filters on a thread of its own, so that typing never waits for it. Starting a
+// filter cancels the one still running, only filters that finish are sent back.
+// `running` is the query and epoch of the last filter started, until it finishes.
+pub struct Worker {
+    filters: Sender<(Filter, Arc<AtomicBool>)>,
+    filtered: Receiver<Filtered>,
+    cancelled: Arc<AtomicBool>,
+    running: Option<(String, usize)>,
+}
+
+impl Worker {
+    pub fn new() -> Worker {
+        let (filters, pending) = channel::<(Filter, Arc<AtomicBool>)>();
+        let (finished, filtered) = channel();
+        thread::spawn(move || {
+            for (filter, cancelled) in pending.iter() {
+                if let Some(result) = filter.run(&cancelled) {
+                    if finished.send(result).is_err() { break }
+                }
+            }
+        });
+        Worker { filters: filters, filtered: filtered, cancelled: Arc::new(AtomicBool::new(false)), running: None }
+    }
+
+    pub fn start(&mut self, filter: Filter) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        self.cancelled = Arc::new(AtomicBool::new(false));
+        self.running = Some((filter.query.clone(), filter.epoch));
+        self.filters.send((filter, self.cancelled.clone())).unwrap();
+    }
+
+    pub fn is_running(&self, filter: &Filter) -> bool {
+        self.running.as_ref().map_or(false, |&(ref query, epoch)| *query == filter.query && epoch == filter.epoch)
+    }
+
+    pub fn finished(&mut self) -> Option<Filtered> {
+        let filtered = self.filtered.try_recv().ok();
+        if let Some(ref filtered) = filtered {
+            if self.running == Some((filtered.query.clone(), filtered.epoch)) { self.running = None }
+        }
+        filtered
+    }
+}
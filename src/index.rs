@@ -0,0 +1,101 @@
+use score::Folding;
+use choices::Choices;
+
+use std::iter::Chain;
+use std::slice;
+use std::sync::Arc;
+
+// Which characters a text contains: one bit for every lower case letter and digit,
+// everything else shares the remaining bits. It is taken from the most permissive
+// folding, so a choice lacking any character of the query can be rejected without
+// looking at its text, whatever case sensitivity the query ends up with.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Signature(u64);
+
+const PERMISSIVE: Folding = Folding { case_sensitive: false, strip_diacritics: true };
+
+impl Signature {
+    pub fn of(text: &str) -> Signature {
+        let folded = PERMISSIVE.fold_query(text);
+        Signature(folded.chars().fold(0, |bits, character| bits | bit(character)))
+    }
+
+    pub fn contains(&self, other: Signature) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn union(&self, other: Signature) -> Signature {
+        Signature(self.0 | other.0)
+    }
+
+    pub fn intersection(&self, other: Signature) -> Signature {
+        Signature(self.0 & other.0)
+    }
+}
+
+fn bit(character: char) -> u64 {
+    let position = match character {
+        'a'..='z' => character as u32 - 'a' as u32,
+        '0'..='9' => character as u32 - '0' as u32 + 26,
+        _ => character as u32 % 28 + 36,
+    };
+    1 << position
+}
+
+// A choice by its index in the input. Every frame of the search holds up to one per
+// choice, so they are kept as small as the number of choices allows.
+pub type Candidate = u32;
+
+// Candidates that only ever grow. Clones share the candidates there were when they were
+// made, so the ones arriving while a clone is around go into `late` instead of copying
+// all the others. Once nothing shares them anymore, the late ones join the rest.
+#[derive(Clone, Debug, Default)]
+pub struct Candidates {
+    shared: Arc<Vec<Candidate>>,
+    late: Vec<Candidate>,
+}
+
+impl Candidates {
+    pub fn len(&self) -> usize {
+        self.shared.len() + self.late.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn extend<I: IntoIterator<Item=Candidate>>(&mut self, arrivals: I) {
+        match Arc::get_mut(&mut self.shared) {
+            Some(shared) => {
+                shared.append(&mut self.late);
+                shared.extend(arrivals);
+            },
+            None => self.late.extend(arrivals),
+        }
+    }
+
+    // Both parts, in input order.
+    pub fn parts(&self) -> [&[Candidate]; 2] {
+        [&self.shared, &self.late]
+    }
+
+    pub fn iter(&self) -> Chain<slice::Iter<Candidate>, slice::Iter<Candidate>> {
+        self.shared.iter().chain(self.late.iter())
+    }
+}
+
+impl From<Vec<Candidate>> for Candidates {
+    fn from(candidates: Vec<Candidate>) -> Candidates {
+        Candidates { shared: Arc::new(candidates), late: Vec::new() }
+    }
+}
+
+// Every choice, before the first keystroke.
+pub fn candidates(choices: &Choices) -> Vec<Candidate> {
+    candidates_from(0, choices)
+}
+
+// The choices from `offset` on, which arrived later.
+pub fn candidates_from(offset: usize, choices: &Choices) -> Vec<Candidate> {
+    (offset as Candidate..choices.len() as Candidate).collect()
+}
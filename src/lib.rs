@@ -2,10 +2,24 @@
 
 extern crate libc;
 extern crate ansi_term;
+extern crate memmap;
+extern crate rayon;
+extern crate regex;
+extern crate unicode_normalization;
+extern crate unicode_width;
 
+pub mod query;
+pub mod prompt;
+pub mod vi;
+pub mod choices;
+pub mod index;
 pub mod score;
+pub mod scorer;
 pub mod search;
+pub mod filter;
+pub mod marks;
 pub mod sorted_result_set;
+pub mod explain;
 pub mod renderer;
 
 pub mod tty;
@@ -13,5 +27,7 @@ pub mod fake_tty;
 
 pub mod ansi;
 pub mod text;
+pub mod width;
 
+pub mod config;
 pub mod screen;
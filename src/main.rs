@@ -4,43 +4,148 @@ extern crate icepick;
 use getopts::Options;
 use std::io::BufRead;
 use std::io;
+use std::fmt::Display;
+use std::process;
+use std::fs::File;
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
 
 use icepick::screen::Screen;
+use icepick::search::Search;
+use icepick::choices::Choices;
+use icepick::config::Config;
+use icepick::sorted_result_set::Tiebreak;
 
 #[allow(dead_code)]
 fn main() {
-    let initial_query = extract_initial_query();
-    let lines = read_lines();
+    let config = extract_config();
+    // Mapped before the terminal is taken over, so a file that cannot be read leaves it as it was.
+    let file = config.file.as_ref().map(|path| map_file(path));
+    if config.explain {
+        let choices = match file {
+            Some(choices) => choices,
+            None => Choices::from(read_lines().iter().collect::<Vec<String>>()),
+        };
+        return explain(choices, config);
+    }
     let mut screen = Screen::new();
 
-    let result = screen.run_search(lines, initial_query);
+    let result = match file {
+        Some(choices) => screen.run_search(choices, config),
+        None => screen.run_search_streaming(read_lines(), config),
+    };
     screen.move_cursor_to_end();
     screen.reset();
-    println!("{}", result.unwrap_or("".to_string()));
+    println!("{}", result.join("\n"));
+}
+
+// Prints how the best matches for the initial query were ranked, without a terminal UI.
+fn explain(choices: Choices, config: Config) {
+    let search = Search::configured(choices, config, 20);
+    for explanation in search.explain() {
+        println!("{}", explanation);
+    }
 }
 
-fn extract_initial_query() -> Option<String> {
+fn extract_config() -> Config {
     let args: Vec<String> = get_args();
     let mut opts = Options::new();
     opts.optopt("s", "search", "initial search query", "");
+    opts.optopt("", "case", "case sensitivity, toggled with Ctrl-T (default: ignore)", "smart|ignore|respect");
+    opts.optflag("", "ignore-diacritics", "match accented characters with their plain letters");
+    opts.optopt("", "scheme", "use path to prefer file name matches and match segments with / (default: default)", "default|path");
+    opts.optopt("", "tiebreak", "how to order equally good matches, before the input order", "length,begin,end,index");
+    opts.optflag("", "explain", "print how the matches for --search are ranked instead of selecting one");
+    opts.optopt("", "typos", "when to show choices that only match with a typo (default: fallback)", "never|fallback|always");
+    opts.optflagopt("m", "multi", "mark choices with Tab to print them all, at most N of them if given", "N");
+    opts.optflag("", "vi", "edit the query with vi keys, Esc switches to normal mode");
+    opts.optopt("", "mark-order", "the order marked choices are printed in (default: input)", "input|marking");
 
     let matches = match opts.parse(&args[1..]) {
         Ok(m) => { m }
-        Err(f) => { panic!(f.to_string()) }
+        Err(f) => { usage_error(&opts, f) }
+    };
+
+    let case = match matches.opt_str("case").map(|name| name.parse()) {
+        Some(Ok(case)) => case,
+        Some(Err(e)) => usage_error(&opts, e),
+        None => Default::default(),
     };
 
-    matches.opt_str("s")
+    let scheme = match matches.opt_str("scheme").map(|name| name.parse()) {
+        Some(Ok(scheme)) => scheme,
+        Some(Err(e)) => usage_error(&opts, e),
+        None => Default::default(),
+    };
+
+    let tolerance = match matches.opt_str("typos").map(|name| name.parse()) {
+        Some(Ok(tolerance)) => tolerance,
+        Some(Err(e)) => usage_error(&opts, e),
+        None => Default::default(),
+    };
+
+    let max_marks = match matches.opt_str("multi").map(|limit| limit.parse::<usize>()) {
+        Some(Ok(limit)) => Some(limit),
+        Some(Err(e)) => usage_error(&opts, format!("invalid --multi limit: {}", e)),
+        None => None,
+    };
+
+    let mark_order = match matches.opt_str("mark-order").map(|name| name.parse()) {
+        Some(Ok(mark_order)) => mark_order,
+        Some(Err(e)) => usage_error(&opts, e),
+        None => Default::default(),
+    };
+
+    let tiebreaks = match matches.opt_str("tiebreak").map(|names| Tiebreak::parse_list(&names)) {
+        Some(Ok(tiebreaks)) => tiebreaks,
+        Some(Err(e)) => usage_error(&opts, e),
+        None => Default::default(),
+    };
+
+    Config {
+        initial_query: matches.opt_str("s"),
+        case: case,
+        ignore_diacritics: matches.opt_present("ignore-diacritics"),
+        scheme: scheme,
+        tolerance: tolerance,
+        tiebreaks: tiebreaks,
+        explain: matches.opt_present("explain"),
+        file: matches.free.first().cloned(),
+        multi: matches.opt_present("multi"),
+        max_marks: max_marks,
+        mark_order: mark_order,
+        vi: matches.opt_present("vi"),
+    }
+}
+
+// Prints what is wrong with the arguments and how to use them instead, then exits.
+fn usage_error<M: Display>(opts: &Options, message: M) -> ! {
+    eprintln!("{}\n\n{}", message, opts.usage("Usage: icepick [options] [FILE]"));
+    process::exit(1)
 }
 
 fn get_args() -> Vec<String> {
     std::env::args().collect()
 }
 
-fn read_lines() -> Vec<String> {
-    let stdin = io::stdin();
-    let reader = stdin.lock();
-    let l = reader.lines().map( |line| {
-        line.unwrap().trim().to_string()
-    }).collect();
-    l
+fn map_file(path: &str) -> Choices {
+    match File::open(path).and_then(|file| Choices::map(&file)) {
+        Ok(choices) => choices,
+        Err(e) => { eprintln!("could not read {}: {}", path, e); process::exit(1) },
+    }
+}
+
+// Reads stdin on a thread of its own, so choices can be picked before it ends.
+fn read_lines() -> Receiver<String> {
+    let (sender, receiver) = channel();
+    thread::spawn(move || {
+        let stdin = io::stdin();
+        for line in stdin.lock().lines() {
+            match line {
+                Ok(line) => if sender.send(line.trim().to_string()).is_err() { break },
+                Err(_) => break,
+            }
+        }
+    });
+    receiver
 }
@@ -0,0 +1,96 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+
+// The order marked choices are printed in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MarkOrder {
+    Input,
+    Marking,
+}
+
+impl Default for MarkOrder {
+    fn default() -> MarkOrder {
+        MarkOrder::Input
+    }
+}
+
+impl FromStr for MarkOrder {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<MarkOrder, String> {
+        match name {
+            "input" => Ok(MarkOrder::Input),
+            "marking" => Ok(MarkOrder::Marking),
+            _ => Err(format!("unknown mark order '{}', expected input or marking", name)),
+        }
+    }
+}
+
+// Choices marked for output, by their index. Marks outlive the query, so choices can be
+// gathered over several searches. Once `limit` choices are marked, no more can be.
+#[derive(Clone, Debug, Default)]
+pub struct Marks {
+    marking: Vec<usize>,
+    marked: HashSet<usize>,
+    limit: Option<usize>,
+}
+
+impl Marks {
+    pub fn new(limit: Option<usize>) -> Marks {
+        Marks { limit: limit, ..Marks::default() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.marking.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.marking.is_empty()
+    }
+
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.limit.map_or(false, |limit| self.len() >= limit)
+    }
+
+    pub fn contains(&self, index: usize) -> bool {
+        self.marked.contains(&index)
+    }
+
+    pub fn mark(&mut self, index: usize) {
+        if self.is_full() || self.contains(index) { return }
+
+        self.marked.insert(index);
+        self.marking.push(index);
+    }
+
+    pub fn unmark(&mut self, index: usize) {
+        if self.marked.remove(&index) {
+            self.marking.retain(|&marked| marked != index);
+        }
+    }
+
+    pub fn toggle(&mut self, index: usize) {
+        if self.contains(index) {
+            self.unmark(index);
+        } else {
+            self.mark(index);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.marking.clear();
+        self.marked.clear();
+    }
+
+    pub fn in_order(&self, order: MarkOrder) -> Vec<usize> {
+        let mut indices = self.marking.clone();
+        if order == MarkOrder::Input {
+            indices.sort();
+        }
+        indices
+    }
+}
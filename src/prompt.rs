@@ -0,0 +1,196 @@
+use std::cmp::min;
+
+const KILL_RING_SIZE: usize = 16;
+
+// The query as it is typed, with a cursor counted in characters. Killed text goes into
+// a ring, most recent last, to be yanked back at the cursor.
+#[derive(Clone, Debug, Default)]
+pub struct Prompt {
+    text: String,
+    cursor: usize,
+    kill_ring: Vec<String>,
+    // Where the last yank started and which entry of the ring it was, until the next edit.
+    yanked: Option<(usize, usize)>,
+}
+
+impl Prompt {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    // The number of characters.
+    pub fn len(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    // The text in front of the cursor.
+    pub fn before_cursor(&self) -> &str {
+        &self.text[..self.offset(self.cursor)]
+    }
+
+    // Replaces the text, leaving the cursor at its end unless the text stays the same.
+    pub fn set_text(&mut self, text: &str) {
+        if self.text == text { return }
+
+        self.text = text.to_string();
+        self.cursor = self.len();
+        self.yanked = None;
+    }
+
+    pub fn insert(&mut self, input: &str) {
+        let offset = self.offset(self.cursor);
+        self.text.insert_str(offset, input);
+        self.cursor += input.chars().count();
+        self.yanked = None;
+    }
+
+    pub fn left(&mut self) {
+        let cursor = self.cursor.saturating_sub(1);
+        self.move_to(cursor);
+    }
+
+    pub fn right(&mut self) {
+        let cursor = self.cursor + 1;
+        self.move_to(cursor);
+    }
+
+    pub fn start(&mut self) {
+        self.move_to(0);
+    }
+
+    pub fn end(&mut self) {
+        let end = self.len();
+        self.move_to(end);
+    }
+
+    pub fn word_left(&mut self) {
+        let start = self.word_start();
+        self.move_to(start);
+    }
+
+    pub fn word_right(&mut self) {
+        let end = self.word_end();
+        self.move_to(end);
+    }
+
+    pub fn delete_backwards(&mut self) {
+        if self.cursor == 0 { return }
+        let cursor = self.cursor;
+        self.remove(cursor - 1, cursor);
+    }
+
+    pub fn delete_forwards(&mut self) {
+        if self.cursor == self.len() { return }
+        let cursor = self.cursor;
+        self.remove(cursor, cursor + 1);
+    }
+
+    // Kills back to the previous whitespace, like Ctrl-W in a shell.
+    pub fn kill_word_backwards(&mut self) {
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut start = self.cursor;
+        while start > 0 && chars[start - 1].is_whitespace() { start -= 1 }
+        while start > 0 && !chars[start - 1].is_whitespace() { start -= 1 }
+        let cursor = self.cursor;
+        self.kill(start, cursor);
+    }
+
+    pub fn kill_to_start(&mut self) {
+        let cursor = self.cursor;
+        self.kill(0, cursor);
+    }
+
+    pub fn kill_to_end(&mut self) {
+        let (cursor, end) = (self.cursor, self.len());
+        self.kill(cursor, end);
+    }
+
+    // Inserts the most recently killed text.
+    pub fn yank(&mut self) {
+        let entry = match self.kill_ring.len() {
+            0 => return,
+            len => len - 1,
+        };
+        let start = self.cursor;
+        let killed = self.kill_ring[entry].clone();
+        self.insert(&killed);
+        self.yanked = Some((start, entry));
+    }
+
+    // Right after a yank, replaces the yanked text with the text killed before it.
+    pub fn yank_previous(&mut self) {
+        let (start, entry) = match self.yanked {
+            Some(yanked) => yanked,
+            None => return,
+        };
+        let previous = if entry == 0 { self.kill_ring.len() - 1 } else { entry - 1 };
+        let cursor = self.cursor;
+        self.remove(start, cursor);
+        let killed = self.kill_ring[previous].clone();
+        self.insert(&killed);
+        self.yanked = Some((start, previous));
+    }
+
+    // Kills the characters from `start` up to `end`.
+    pub fn kill(&mut self, start: usize, end: usize) {
+        if start == end { return }
+
+        self.copy(start, end);
+        self.remove(start, end);
+    }
+
+    // Puts the characters from `start` up to `end` into the kill ring, without removing them.
+    pub fn copy(&mut self, start: usize, end: usize) {
+        if start == end { return }
+
+        let copied = self.text[self.offset(start)..self.offset(end)].to_string();
+        if self.kill_ring.len() == KILL_RING_SIZE {
+            self.kill_ring.remove(0);
+        }
+        self.kill_ring.push(copied);
+        self.yanked = None;
+    }
+
+    // Removes the characters from `start` up to `end` and leaves the cursor at `start`.
+    fn remove(&mut self, start: usize, end: usize) {
+        let (from, to) = (self.offset(start), self.offset(end));
+        self.text.replace_range(from..to, "");
+        self.cursor = start;
+        self.yanked = None;
+    }
+
+    pub fn move_to(&mut self, cursor: usize) {
+        self.cursor = min(cursor, self.len());
+        self.yanked = None;
+    }
+
+    // Words are runs of letters and digits.
+    fn word_start(&self) -> usize {
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut start = self.cursor;
+        while start > 0 && !chars[start - 1].is_alphanumeric() { start -= 1 }
+        while start > 0 && chars[start - 1].is_alphanumeric() { start -= 1 }
+        start
+    }
+
+    fn word_end(&self) -> usize {
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut end = self.cursor;
+        while end < chars.len() && !chars[end].is_alphanumeric() { end += 1 }
+        while end < chars.len() && chars[end].is_alphanumeric() { end += 1 }
+        end
+    }
+
+    // The byte offset of the character at `position`.
+    fn offset(&self, position: usize) -> usize {
+        self.text.char_indices().nth(position).map_or(self.text.len(), |(offset, _)| offset)
+    }
+}
@@ -0,0 +1,114 @@
+// Parses the extended query syntax:
+//
+//   foo      fuzzy match
+//   'foo     exact substring match
+//   ^foo     prefix match
+//   foo$     suffix match
+//   ^foo$    the whole line has to be foo
+//   !foo     must not contain foo (also !^foo, !foo$ and !'foo)
+//   a | b    either a or b has to match
+//
+// Space separated terms all have to match, in any order.
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Kind {
+    Fuzzy,
+    Exact,
+    Prefix,
+    Suffix,
+    Line,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Term {
+    pub kind: Kind,
+    pub text: String,
+    pub negated: bool,
+}
+
+// Every group has to match and a group matches when any of its terms does.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Query {
+    pub groups: Vec<Vec<Term>>,
+}
+
+impl Query {
+    pub fn parse(query: &str) -> Query {
+        let mut groups: Vec<Vec<Term>> = Vec::new();
+        let mut continues_group = false;
+
+        for word in query.split(' ').filter(|word| !word.is_empty()) {
+            if word == "|" {
+                continues_group = !groups.is_empty();
+                continue;
+            }
+            match Term::parse(word) {
+                Some(term) => {
+                    if continues_group {
+                        groups.last_mut().unwrap().push(term);
+                    } else {
+                        groups.push(vec![term]);
+                    }
+                },
+                None => {},
+            };
+            continues_group = false;
+        }
+
+        Query { groups: groups }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    // Negations and alternatives can match more choices when the query gets longer.
+    pub fn narrows_when_appending(&self) -> bool {
+        self.groups.iter().all(|group| group.len() == 1 && !group[0].negated)
+    }
+
+    // Whether `query`, which starts with `prefix`, still anchors wherever `prefix` does.
+    // Typing on right after a trailing `$` turns it into a character to match instead.
+    pub fn keeps_anchors(prefix: &str, query: &str) -> bool {
+        !prefix.ends_with('$') || query[prefix.len()..].chars().next().map_or(true, |next| next == ' ')
+    }
+}
+
+impl Term {
+    fn parse(word: &str) -> Option<Term> {
+        let (negated, word) = strip_prefix(word, '!');
+        let (anchored_start, word) = strip_prefix(word, '^');
+        let (exact, word) = strip_prefix(word, '\'');
+        let (anchored_end, word) = strip_suffix(word, '$');
+
+        let kind = match (anchored_start, anchored_end) {
+            (true, true) => Kind::Line,
+            (true, false) => Kind::Prefix,
+            (false, true) => Kind::Suffix,
+            (false, false) if exact || negated => Kind::Exact,
+            (false, false) => Kind::Fuzzy,
+        };
+
+        if word.is_empty() {
+            None
+        } else {
+            Some(Term { kind: kind, text: word.to_string(), negated: negated })
+        }
+    }
+}
+
+fn strip_prefix(word: &str, marker: char) -> (bool, &str) {
+    if word.starts_with(marker) {
+        (true, &word[marker.len_utf8()..])
+    } else {
+        (false, word)
+    }
+}
+
+fn strip_suffix(word: &str, marker: char) -> (bool, &str) {
+    if word.ends_with(marker) {
+        (true, &word[..word.len() - marker.len_utf8()])
+    } else {
+        (false, word)
+    }
+}
@@ -1,25 +1,114 @@
 use search::Search;
-use text::Text;
+use score::{Case, Match};
+use text::{Text, Window, ELLIPSIS};
+use width::{char_width, display_width};
+use std::cmp::{min, max};
+
+pub const MARKED: &'static str = "* ";
+pub const UNMARKED: &'static str = "  ";
 
 pub struct Renderer;
 
 impl Renderer {
-    pub fn render<'a>(&'a self, search: &'a Search) -> Vec<Text> {
+    pub fn render<'a>(&'a self, search: &'a Search, width: usize) -> Vec<Text> {
         let mut result = Vec::new();
         result.push(Text::Normal(self.header(search)));
 
+        // Choices share the width with a gutter for marks.
+        let width = if search.multi { width.saturating_sub(MARKED.len()) } else { width };
+        let matches = search.matches();
         for position in 0..search.visible_limit {
-            let element = match search.result.get(position) {
-                Some(choice) if position == search.current => Text::Highlight(choice.original.clone()),
-                Some(choice) => Text::Colored(choice.clone()),
+            let element = match matches.get(position) {
+                Some(choice) if search.top + position == search.current => Text::Highlight(choice.clone(), self.window(choice, width, search.scroll)),
+                Some(choice) => Text::Colored(choice.clone(), self.window(choice, width, 0)),
                 None => Text::Blank
             };
+            let element = match element {
+                Text::Blank => element,
+                _ if search.multi => {
+                    let gutter = if search.is_marked(search.top + position) { MARKED } else { UNMARKED };
+                    Text::Gutter(gutter, Box::new(element))
+                },
+                _ => element,
+            };
             result.push(element);
         }
         result
     }
 
+    // The number of matches, any mode that differs from the default and the query.
     pub fn header(&self, search: &Search) -> String {
-        format!("{} > {}", search.num_matches(), search.query)
+        format!("{} > {}", self.status(search), search.query)
+    }
+
+    // The column of the header the query is edited at.
+    pub fn cursor_column(&self, search: &Search) -> usize {
+        display_width(&format!("{} > {}", self.status(search), search.prompt.before_cursor()))
+    }
+
+    fn status(&self, search: &Search) -> String {
+        let mut header = search.num_matches().to_string();
+        if search.case != Case::Ignore {
+            header.push_str(&format!(" [{}]", search.case.name()));
+        }
+        if search.multi {
+            match search.marks.limit() {
+                Some(limit) => header.push_str(&format!(" [{}/{} marked]", search.marks.len(), limit)),
+                None => header.push_str(&format!(" [{} marked]", search.marks.len())),
+            }
+        }
+        if let Some(ref vi) = search.vi {
+            header.push_str(&format!(" [{}]", vi.mode.name()));
+        }
+        if search.loading {
+            header.push_str(" [loading]");
+        }
+        if search.filtering {
+            header.push_str(" [filtering…]");
+        }
+        header
+    }
+
+    // Scrolls a choice that is too long for the screen so that its matched characters are visible.
+    // `scroll` moves the window further by that many characters.
+    pub fn window(&self, matching: &Match, width: usize, scroll: isize) -> Window {
+        let columns = column_offsets(matching.original);
+        let length = columns.len() - 1;
+        let total = columns[length];
+
+        if total <= width {
+            return Window { start: 0, width: width };
+        }
+
+        let last_start = (0..length).find(|&idx| total - columns[idx] + ELLIPSIS.len() <= width).unwrap_or(length);
+        let start = max(0, self.start_showing_match(matching, &columns, width) as isize + scroll);
+        Window { start: min(start as usize, last_start), width: width }
+    }
+
+    fn start_showing_match(&self, matching: &Match, columns: &Vec<usize>, width: usize) -> usize {
+        let (first, last) = match (matching.positions.first(), matching.positions.last()) {
+            (Some(&first), Some(&last)) => (first, last),
+            _ => return 0,
+        };
+
+        let end = columns[last + 1];
+        if end + ELLIPSIS.len() <= width {
+            return 0;
+        }
+
+        let visible = width.saturating_sub(2 * ELLIPSIS.len());
+        let start_column = min(end.saturating_sub(visible), columns[first]);
+        columns.iter().position(|&column| column >= start_column).unwrap_or(0)
+    }
+}
+
+// The column every character starts at, followed by the total width.
+fn column_offsets(text: &str) -> Vec<usize> {
+    let mut columns = vec![0];
+    let mut column = 0;
+    for character in text.chars() {
+        column += char_width(character, column);
+        columns.push(column);
     }
+    columns
 }
@@ -1,6 +1,9 @@
-use std::cmp::min;
 use std::ascii::AsciiExt;
 use std::ops::Range;
+use std::str::FromStr;
+use unicode_normalization::char::{decompose_canonical, is_combining_mark};
+use regex::Regex;
+use std::cmp::{max, min};
 
 #[derive(Clone, Debug,PartialEq)]
 pub struct Quality(pub f32);
@@ -12,11 +15,200 @@ impl Quality {
     }
 }
 
+// How upper and lower case letters in the query and the choices are compared.
+// `Smart` ignores case until the query contains an upper case letter.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Case {
+    Ignore,
+    Smart,
+    Respect,
+}
+
+impl Case {
+    pub fn is_sensitive(&self, query: &str) -> bool {
+        match *self {
+            Case::Ignore => false,
+            Case::Smart => query.chars().any(|c| c.is_uppercase()),
+            Case::Respect => true,
+        }
+    }
+
+    pub fn next(&self) -> Case {
+        match *self {
+            Case::Ignore => Case::Smart,
+            Case::Smart => Case::Respect,
+            Case::Respect => Case::Ignore,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Case::Ignore => "ignore",
+            Case::Smart => "smart",
+            Case::Respect => "respect",
+        }
+    }
+}
+
+impl Default for Case {
+    fn default() -> Case {
+        Case::Ignore
+    }
+}
+
+impl FromStr for Case {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Case, String> {
+        match name {
+            "ignore" => Ok(Case::Ignore),
+            "smart" => Ok(Case::Smart),
+            "respect" => Ok(Case::Respect),
+            _ => Err(format!("Unknown case mode '{}', expected smart, ignore or respect", name)),
+        }
+    }
+}
+
+// What kind of text the choices are, which decides how fuzzy terms are scored.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scheme {
+    Default,
+    Path,
+}
+
+impl Default for Scheme {
+    fn default() -> Scheme {
+        Scheme::Default
+    }
+}
+
+impl FromStr for Scheme {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Scheme, String> {
+        match name {
+            "default" => Ok(Scheme::Default),
+            "path" => Ok(Scheme::Path),
+            _ => Err(format!("Unknown scheme '{}', expected default or path", name)),
+        }
+    }
+}
+
+// How the query and the choices are normalized before they are compared.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Folding {
+    pub case_sensitive: bool,
+    pub strip_diacritics: bool,
+}
+
+// Folded text together with the index of the original character every folded character came from.
+struct Folded {
+    chars: Vec<char>,
+    origins: Vec<usize>,
+}
+
+impl Folding {
+    pub fn new(case: Case, query: &str, strip_diacritics: bool) -> Folding {
+        Folding { case_sensitive: case.is_sensitive(query), strip_diacritics: strip_diacritics }
+    }
+
+    // Prepares a query for the `score` functions below.
+    pub fn fold_query(&self, query: &str) -> String {
+        self.fold(query).chars.into_iter().collect()
+    }
+
+    fn fold(&self, text: &str) -> Folded {
+        let mut chars = Vec::with_capacity(text.len());
+        let mut origins = Vec::with_capacity(text.len());
+
+        for (idx, character) in text.chars().enumerate() {
+            self.fold_char(character, |folded| {
+                chars.push(folded);
+                origins.push(idx);
+            });
+        }
+        Folded { chars: chars, origins: origins }
+    }
+
+    fn fold_char<F: FnMut(char)>(&self, character: char, mut f: F) {
+        if self.strip_diacritics && !character.is_ascii() {
+            decompose_canonical(character, |part| {
+                if !is_combining_mark(part) { self.fold_case(part, &mut f) }
+            });
+        } else {
+            self.fold_case(character, &mut f);
+        }
+    }
+
+    fn fold_case<F: FnMut(char)>(&self, character: char, f: &mut F) {
+        if self.case_sensitive {
+            f(character);
+        } else if character.is_ascii() {
+            f(character.to_ascii_lowercase());
+        } else {
+            match character {
+                'ß' | 'ẞ' => { f('s'); f('s'); },
+                'ς' => f('σ'),
+                _ => for lower in character.to_lowercase() { f(lower) },
+            }
+        }
+    }
+}
+
+// The characters a choice folds into, as the functions below compare them with a folded query.
+trait Haystack {
+    fn len(&self) -> usize;
+    fn at(&self, idx: usize) -> char;
+}
+
+impl Haystack for [char] {
+    fn len(&self) -> usize {
+        <[char]>::len(self)
+    }
+
+    fn at(&self, idx: usize) -> char {
+        self[idx]
+    }
+}
+
+// Every byte of an ASCII choice folds into exactly one character, so it is compared
+// byte by byte instead of being folded into a copy first.
+struct Ascii<'a> {
+    bytes: &'a [u8],
+    case_sensitive: bool,
+}
+
+impl<'a> Ascii<'a> {
+    fn new(choice: &'a str, folding: Folding) -> Ascii<'a> {
+        Ascii { bytes: choice.as_bytes(), case_sensitive: folding.case_sensitive }
+    }
+}
+
+impl<'a> Haystack for Ascii<'a> {
+    fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    fn at(&self, idx: usize) -> char {
+        let byte = self.bytes[idx];
+        if self.case_sensitive { byte as char } else { byte.to_ascii_lowercase() as char }
+    }
+}
+
+impl Folded {
+    fn original_positions(&self, positions: &[usize]) -> Vec<usize> {
+        let mut original: Vec<usize> = positions.iter().map(|&position| self.origins[position]).collect();
+        original.dedup();
+        original
+    }
+}
+
 #[derive(Clone, Debug,PartialEq)]
 pub struct Match<'a> {
     pub quality: Quality,
     pub range: Range<usize>,
-    pub original: &'a String,
+    pub positions: Vec<usize>,
+    pub original: &'a str,
 
 }
 
@@ -29,94 +221,545 @@ impl <'a> Match<'a> {
         input[start..end].to_string(),
         input[end..].to_string())
     }
+
+    pub fn is_matched(&self, position: usize) -> bool {
+        self.positions.binary_search(&position).is_ok()
+    }
 }
 
 impl <'a>Match<'a>{
-    pub fn new(quality: Quality, range: Range<usize>, original: &'a String) -> Match<'a> {
-        Match { quality: quality, range: range, original: original }
+    // `positions` are the indices of the matched characters, in ascending order.
+    pub fn new(quality: Quality, positions: Vec<usize>, original: &'a str) -> Match<'a> {
+        let range = byte_range(original, &positions);
+        Match { quality: quality, range: range, positions: positions, original: original }
     }
 
-    pub fn with_empty_range(original: &'a String) -> Match<'a> {
-        Match::new(Quality(1.0), Range{start: 0,end: 0}, original)
+    pub fn with_empty_range(original: &'a str) -> Match<'a> {
+        Match::new(Quality(1.0), vec![], original)
     }
 }
 
-pub fn score<'a>(choice: &'a String, query: &String) -> Option<Match<'a>> {
-    let choice_length = choice.len() as f32;
-    let query_length = query.len() as f32;
+pub fn score<'a>(choice: &'a str, query: &String, folding: Folding) -> Option<Match<'a>> {
+    if query.len() == 0 { return Some(Match::with_empty_range(choice)) }
 
-    if query_length == 0.0 { return Some(Match::with_empty_range(choice)) }
-    let lower_choice = choice.to_ascii_lowercase();
+    let haystack = folding.fold(choice);
+    let needle: Vec<char> = query.chars().collect();
+    let choice_length = haystack.chars.len() as f32;
+    let query_length = needle.len() as f32;
 
+    compute_match_positions(&haystack.chars, &needle).map(|positions| {
+        let quality = Quality( (query_length / span(&positions) as f32) / choice_length);
+        Match::new(quality, haystack.original_positions(&positions), choice)
+    })
+}
 
-    // TODO convert this over to compute_match_length(...).map(...)
-    match compute_match_length(&lower_choice, query) {
-        Some((start, match_length)) => {
-            let quality = Quality( (query_length / match_length as f32) / choice_length);
-            let substring = Range {start: start, end: start+match_length};
-            Some(Match::new(quality, substring, choice))
-        },
-        None => None,
+pub fn prefix<'a>(choice: &'a str, query: &String, folding: Folding) -> Option<Match<'a>> {
+    if query.len() == 0 { return Some(Match::with_empty_range(choice)) }
+
+    let haystack = folding.fold(choice);
+    let needle: Vec<char> = query.chars().collect();
+
+    if haystack.chars.starts_with(&needle) {
+        let positions: Vec<usize> = (0..needle.len()).collect();
+        Some(exact_match(&haystack, &positions, choice))
+    } else {
+        None
     }
 }
 
-fn slice_shift_char(line: &str) -> Option<(char, &str)> {
-    if line.is_empty() {
-        None
+pub fn suffix<'a>(choice: &'a str, query: &String, folding: Folding) -> Option<Match<'a>> {
+    if query.len() == 0 { return Some(Match::with_empty_range(choice)) }
+
+    let haystack = folding.fold(choice);
+    let needle: Vec<char> = query.chars().collect();
+
+    if haystack.chars.ends_with(&needle) {
+        let length = haystack.chars.len();
+        let positions: Vec<usize> = (length - needle.len()..length).collect();
+        Some(exact_match(&haystack, &positions, choice))
     } else {
-        let mut chars = line.chars();
-        let ch = chars.next().unwrap();
-        let len = line.len();
-        let next_s = &line[ch.len_utf8().. len];
-        Some((ch, next_s))
+        None
     }
 }
 
-fn compute_match_length(choice: &String, query: &String) -> Option<(usize, usize)> {
-    if query.len() == 0 {
-        return None;
+pub fn substring<'a>(choice: &'a str, query: &String, folding: Folding) -> Option<Match<'a>> {
+    if query.len() == 0 { return Some(Match::with_empty_range(choice)) }
+
+    let haystack = folding.fold(choice);
+    let needle: Vec<char> = query.chars().collect();
+
+    haystack.chars.windows(needle.len()).position(|window| window == &needle[..]).map(|first| {
+        let positions: Vec<usize> = (first..first + needle.len()).collect();
+        exact_match(&haystack, &positions, choice)
+    })
+}
+
+pub fn line<'a>(choice: &'a str, query: &String, folding: Folding) -> Option<Match<'a>> {
+    if query.len() == 0 { return Some(Match::with_empty_range(choice)) }
+
+    let haystack = folding.fold(choice);
+    let needle: Vec<char> = query.chars().collect();
+
+    if haystack.chars == needle {
+        let positions: Vec<usize> = (0..choice.chars().count()).collect();
+        Some(Match::new(Quality(1.0), positions, choice))
+    } else {
+        None
     }
-    let (first, rest) = slice_shift_char(query).unwrap();
+}
+
+// Case is left to the pattern, which is expected to be compiled with `(?i)` when case is ignored.
+pub fn pattern<'a>(choice: &'a str, pattern: &Regex, folding: Folding) -> Option<Match<'a>> {
+    let folding = Folding { case_sensitive: true, ..folding };
+    let haystack = folding.fold(choice);
+    let text: String = haystack.chars.iter().cloned().collect();
+
+    pattern.find(&text).map(|(start, end)| {
+        let first = text[..start].chars().count();
+        let length = text[start..end].chars().count();
+        let quality = Quality(length as f32 / max(haystack.chars.len(), 1) as f32);
+        let positions: Vec<usize> = (first..first + length).collect();
+        Match::new(quality, haystack.original_positions(&positions), choice)
+    })
+}
+
+fn exact_match<'a>(haystack: &Folded, positions: &[usize], choice: &'a str) -> Match<'a> {
+    let quality = Quality(positions.len() as f32 / haystack.chars.len() as f32);
+    Match::new(quality, haystack.original_positions(positions), choice)
+}
 
-    let impossible_match = choice.len() + 1;
-    let mut shortest_match = impossible_match;
-    let mut shortest_start = impossible_match;
+fn compute_match_positions(choice: &[char], query: &[char]) -> Option<Vec<usize>> {
+    let (first, rest) = match query.split_first() {
+        Some(split) => split,
+        None => return None,
+    };
 
-    for_each_beginning(choice, first, |beginning| {
-        match match_length_from(choice, rest, beginning) {
-            Some(length) => {
-                             shortest_match = min(length, shortest_match);
-                             shortest_start = beginning;
+    let mut shortest: Option<Vec<usize>> = None;
+
+    for_each_beginning(choice, *first, |beginning| {
+        match match_positions_from(choice, rest, beginning) {
+            Some(positions) => {
+                let is_shorter = shortest.as_ref().map_or(true, |s| span(&positions) < span(s));
+                if is_shorter { shortest = Some(positions) }
             },
             None => {},
         };
     });
 
-    if shortest_match == impossible_match {None} else {Some((shortest_start, shortest_match))}
+    shortest
+}
+
+fn span(positions: &Vec<usize>) -> usize {
+    positions[positions.len() - 1] - positions[0] + 1
 }
 
-fn for_each_beginning<F: FnMut(usize)>(choice: &String, beginning: char, mut f: F) {
-    for (idx, character) in choice.chars().enumerate() {
+fn for_each_beginning<F: FnMut(usize)>(choice: &[char], beginning: char, mut f: F) {
+    for (idx, &character) in choice.iter().enumerate() {
         if character == beginning {
             f(idx);
         }
     }
 }
 
-fn match_length_from(choice: &String, query: &str, beginning: usize) -> Option<usize> {
+fn match_positions_from(choice: &[char], query: &[char], beginning: usize) -> Option<Vec<usize>> {
+    let mut positions = vec![beginning];
     let mut match_index = beginning;
 
-    for query_char in query.chars() {
+    for &query_char in query.iter() {
        match find_first_after(choice, query_char, match_index + 1) {
            Some(n) => match_index = n,
            None => return None,
        };
+       positions.push(match_index);
     }
-    Some(match_index - beginning + 1)
+    Some(positions)
 }
 
-fn find_first_after(choice: &String, query: char, offset: usize) -> Option<usize> {
+fn find_first_after(choice: &[char], query: char, offset: usize) -> Option<usize> {
     choice[offset..]
-        .find(query)
+        .iter()
+        .position(|&character| character == query)
         .map(|index| index + offset)
 }
+
+pub const SCORE_GAP_LEADING: f32 = -0.005;
+pub const SCORE_GAP_TRAILING: f32 = -0.005;
+pub const SCORE_GAP_INNER: f32 = -0.01;
+const SCORE_MATCH_CONSECUTIVE: f32 = 1.0;
+const SCORE_MATCH_SLASH: f32 = 0.9;
+const SCORE_MATCH_WORD: f32 = 0.8;
+const SCORE_MATCH_CAPITAL: f32 = 0.7;
+const SCORE_MATCH_DOT: f32 = 0.6;
+
+// Why `fuzzy` scores a matched character higher than others.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Bonus {
+    Consecutive,
+    Slash,
+    Word,
+    Capital,
+    Dot,
+}
+
+impl Bonus {
+    // The bonus for a character right after `last`, not counting consecutive matches.
+    pub fn between(last: char, current: char) -> Option<Bonus> {
+        if !current.is_alphanumeric() {
+            return None;
+        }
+        match last {
+            '/' => Some(Bonus::Slash),
+            '-' | '_' | ' ' => Some(Bonus::Word),
+            '.' => Some(Bonus::Dot),
+            _ if last.is_lowercase() && current.is_uppercase() => Some(Bonus::Capital),
+            _ => None,
+        }
+    }
+
+    pub fn score(&self) -> f32 {
+        match *self {
+            Bonus::Consecutive => SCORE_MATCH_CONSECUTIVE,
+            Bonus::Slash => SCORE_MATCH_SLASH,
+            Bonus::Word => SCORE_MATCH_WORD,
+            Bonus::Capital => SCORE_MATCH_CAPITAL,
+            Bonus::Dot => SCORE_MATCH_DOT,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Bonus::Consecutive => "consecutive",
+            Bonus::Slash => "after slash",
+            Bonus::Word => "word start",
+            Bonus::Capital => "capital",
+            Bonus::Dot => "after dot",
+        }
+    }
+}
+
+// Scores a choice the way fzy does: every query character has to be matched in
+// order, matches right after a separator or on a camelCase hump earn a bonus,
+// gaps cost a little and the best alignment is found with dynamic programming.
+pub fn fuzzy<'a>(choice: &'a str, query: &String, folding: Folding) -> Option<Match<'a>> {
+    if query.len() == 0 { return Some(Match::with_empty_range(choice)) }
+
+    let needle: Vec<char> = query.chars().collect();
+    if choice.is_ascii() {
+        fuzzy_in(&Ascii::new(choice, folding), &needle, || original_bonus(choice)).map(|(quality, positions)| {
+            Match::new(Quality(quality), positions, choice)
+        })
+    } else {
+        let folded = folding.fold(choice);
+        fuzzy_in(&folded.chars[..], &needle, || compute_bonus(choice, &folded)).map(|(quality, positions)| {
+            Match::new(Quality(quality), folded.original_positions(&positions), choice)
+        })
+    }
+}
+
+// The quality of the best alignment and the positions it matches in the haystack.
+// The bonus of every haystack character is only worked out once it is known to match.
+fn fuzzy_in<H: Haystack + ?Sized, F: FnOnce() -> Vec<f32>>(haystack: &H, needle: &[char], bonus: F) -> Option<(f32, Vec<usize>)> {
+    if !is_subsequence(haystack, needle) { return None }
+
+    if haystack.len() == needle.len() {
+        Some((f32::INFINITY, (0..needle.len()).collect()))
+    } else {
+        let (d, m) = fill_score_matrices(haystack, needle, &bonus());
+        let positions = backtrack_positions(&d, &m, needle.len(), haystack.len());
+        Some((m[needle.len() - 1][haystack.len() - 1], positions))
+    }
+}
+
+// The quality `fuzzy` gives a run of consecutive characters at `positions`: the bonus of
+// the first one, a consecutive match for every other one and a gap for every character
+// around the run. Unlike `fuzzy`, a run covering the whole choice is not worth infinitely
+// much, so it still adds up with other qualities.
+pub fn consecutive(choice: &str, positions: &[usize]) -> f32 {
+    let (first, last) = match (positions.first(), positions.last()) {
+        (Some(&first), Some(&last)) => (first, last),
+        _ => return 0.0,
+    };
+    let chars: Vec<char> = choice.chars().collect();
+    let before = if first == 0 { '/' } else { chars[first - 1] };
+
+    bonus_for(before, chars[first]) + (positions.len() - 1) as f32 * SCORE_MATCH_CONSECUTIVE +
+        first as f32 * SCORE_GAP_LEADING + (chars.len() - last - 1) as f32 * SCORE_GAP_TRAILING
+}
+
+// Whether `fuzzy` matches the choice, without scoring it.
+pub fn fuzzy_matches(choice: &str, query: &String, folding: Folding) -> bool {
+    let needle: Vec<char> = query.chars().collect();
+    if choice.is_ascii() {
+        is_subsequence(&Ascii::new(choice, folding), &needle)
+    } else {
+        is_subsequence(&folding.fold(choice).chars[..], &needle)
+    }
+}
+
+// The best quality `fuzzy` can give a choice for a folded query, without folding the
+// choice: the first matched character earns at most a slash bonus, every other one a
+// consecutive match, and every character left over costs at least a leading or trailing
+// gap. Only ASCII characters are counted, the others might fold into nothing. A query
+// that is not ASCII might be matched by fewer characters than it has, so it is not bounded.
+pub fn fuzzy_upper_bound(choice: &str, query: &str) -> f32 {
+    let length = choice.bytes().filter(|byte| byte.is_ascii()).count();
+    let query_length = query.len();
+    if query_length == 0 || !query.is_ascii() || length <= query_length { return f32::INFINITY }
+
+    let gap = SCORE_GAP_LEADING.max(SCORE_GAP_TRAILING);
+    SCORE_MATCH_SLASH + (query_length - 1) as f32 * SCORE_MATCH_CONSECUTIVE + (length - query_length) as f32 * gap
+}
+
+fn is_subsequence<H: Haystack + ?Sized>(haystack: &H, needle: &[char]) -> bool {
+    let mut remaining = needle.iter().peekable();
+    for idx in 0..haystack.len() {
+        if remaining.peek() == Some(&&haystack.at(idx)) {
+            remaining.next();
+        }
+    }
+    remaining.peek().is_none()
+}
+
+// The bonus of every character of the choice, before it is folded.
+fn original_bonus(choice: &str) -> Vec<f32> {
+    let mut last = '/';
+    choice.chars().map(|current| {
+        let bonus = bonus_for(last, current);
+        last = current;
+        bonus
+    }).collect()
+}
+
+// Only the first of the characters an original character folds into can earn a bonus.
+fn compute_bonus(choice: &str, folded: &Folded) -> Vec<f32> {
+    let original = original_bonus(choice);
+
+    folded.origins.iter().enumerate().map(|(idx, &origin)| {
+        if idx > 0 && folded.origins[idx - 1] == origin { 0.0 } else { original[origin] }
+    }).collect()
+}
+
+fn bonus_for(last: char, current: char) -> f32 {
+    Bonus::between(last, current).map_or(0.0, |bonus| bonus.score())
+}
+
+// `d[i][j]` is the best score for the first `i + 1` query characters that ends
+// with a match at `j`, `m[i][j]` the best score that ends anywhere up to `j`.
+fn fill_score_matrices<H: Haystack + ?Sized>(haystack: &H, needle: &[char], bonus: &[f32]) -> (Vec<Vec<f32>>, Vec<Vec<f32>>) {
+    let n = needle.len();
+    let mut d = vec![vec![f32::NEG_INFINITY; haystack.len()]; n];
+    let mut m = vec![vec![f32::NEG_INFINITY; haystack.len()]; n];
+
+    for i in 0..n {
+        let mut previous = f32::NEG_INFINITY;
+        let gap_score = if i == n - 1 { SCORE_GAP_TRAILING } else { SCORE_GAP_INNER };
+
+        for j in 0..haystack.len() {
+            if needle[i] == haystack.at(j) {
+                let score = if i == 0 {
+                    (j as f32 * SCORE_GAP_LEADING) + bonus[j]
+                } else if j > 0 {
+                    (m[i - 1][j - 1] + bonus[j]).max(d[i - 1][j - 1] + SCORE_MATCH_CONSECUTIVE)
+                } else {
+                    f32::NEG_INFINITY
+                };
+                d[i][j] = score;
+                previous = score.max(previous + gap_score);
+            } else {
+                previous = previous + gap_score;
+            }
+            m[i][j] = previous;
+        }
+    }
+    (d, m)
+}
+
+fn backtrack_positions(d: &Vec<Vec<f32>>, m: &Vec<Vec<f32>>, n: usize, length: usize) -> Vec<usize> {
+    let mut positions = vec![0; n];
+    let mut match_required = false;
+    let mut j = length;
+
+    for i in (0..n).rev() {
+        while j > 0 {
+            j -= 1;
+            if d[i][j] != f32::NEG_INFINITY && (match_required || d[i][j] == m[i][j]) {
+                match_required = i > 0 && j > 0 && m[i][j] == d[i - 1][j - 1] + SCORE_MATCH_CONSECUTIVE;
+                positions[i] = j;
+                break;
+            }
+        }
+    }
+    positions
+}
+
+const MAX_TYPOS: usize = 2;
+const SCORE_TYPO: f32 = -1000.0;
+
+// Scores a choice allowing a few typos in the query: a character typed wrong,
+// two neighbouring characters swapped, or a character that is not in the choice
+// at all. Every typo costs far more than any gap does in `fuzzy`, so these
+// matches always rank below the ones without typos. Short queries allow none.
+pub fn tolerant<'a>(choice: &'a str, query: &String, folding: Folding) -> Option<Match<'a>> {
+    let needle: Vec<char> = query.chars().collect();
+    let allowed = min(MAX_TYPOS, needle.len() / 4);
+    if allowed == 0 { return None }
+
+    if choice.is_ascii() {
+        tolerant_in(&Ascii::new(choice, folding), &needle, allowed).map(|(quality, positions)| {
+            Match::new(Quality(quality), positions, choice)
+        })
+    } else {
+        let folded = folding.fold(choice);
+        tolerant_in(&folded.chars[..], &needle, allowed).map(|(quality, positions)| {
+            Match::new(Quality(quality), folded.original_positions(&positions), choice)
+        })
+    }
+}
+
+fn tolerant_in<H: Haystack + ?Sized>(haystack: &H, needle: &[char], allowed: usize) -> Option<(f32, Vec<usize>)> {
+    let typos = fill_typo_matrix(haystack, needle);
+    let errors = typos[needle.len()][haystack.len()];
+    if errors > allowed { return None }
+
+    let positions = backtrack_typos(&typos, haystack, needle);
+    let gaps = if positions.is_empty() { 0 } else { span(&positions) - positions.len() };
+    Some((SCORE_TYPO * errors as f32 + SCORE_GAP_INNER * gaps as f32, positions))
+}
+
+// typos[i][j] holds the fewest typos needed to find the first i query
+// characters within the first j characters of the choice.
+fn fill_typo_matrix<H: Haystack + ?Sized>(haystack: &H, needle: &[char]) -> Vec<Vec<usize>> {
+    let mut typos = vec![vec![0; haystack.len() + 1]; needle.len() + 1];
+
+    for i in 1..needle.len() + 1 {
+        typos[i][0] = i;
+        for j in 1..haystack.len() + 1 {
+            let substituted = if needle[i - 1] == haystack.at(j - 1) { 0 } else { 1 };
+            let mut best = min(typos[i][j - 1], typos[i - 1][j - 1] + substituted);
+            best = min(best, typos[i - 1][j] + 1);
+            if is_transposed(haystack, needle, i, j) {
+                best = min(best, typos[i - 2][j - 2] + 1);
+            }
+            typos[i][j] = best;
+        }
+    }
+    typos
+}
+
+fn is_transposed<H: Haystack + ?Sized>(haystack: &H, needle: &[char], i: usize, j: usize) -> bool {
+    i >= 2 && j >= 2 && needle[i - 1] != needle[i - 2] &&
+        needle[i - 1] == haystack.at(j - 2) && needle[i - 2] == haystack.at(j - 1)
+}
+
+// Only characters that were actually found, swapped or not, are reported.
+fn backtrack_typos<H: Haystack + ?Sized>(typos: &Vec<Vec<usize>>, haystack: &H, needle: &[char]) -> Vec<usize> {
+    let mut positions = Vec::new();
+    let (mut i, mut j) = (needle.len(), haystack.len());
+
+    while i > 0 {
+        let current = typos[i][j];
+        if j > 0 && needle[i - 1] == haystack.at(j - 1) && current == typos[i - 1][j - 1] {
+            positions.push(j - 1);
+            i -= 1;
+            j -= 1;
+        } else if is_transposed(haystack, needle, i, j) && current == typos[i - 2][j - 2] + 1 {
+            positions.push(j - 1);
+            positions.push(j - 2);
+            i -= 2;
+            j -= 2;
+        } else if j > 0 && current == typos[i][j - 1] {
+            j -= 1;
+        } else if j > 0 && current == typos[i - 1][j - 1] + 1 {
+            i -= 1;
+            j -= 1;
+        } else {
+            i -= 1;
+        }
+    }
+    positions.reverse();
+    positions
+}
+
+const SCORE_MATCH_BASENAME: f32 = 2.0;
+
+// Scores a choice as a file path. A query without a slash is matched fuzzily,
+// preferably within the file name alone. A query with slashes has to match
+// the start of path segments in order, so `s/scr` finds `src/screen.rs`.
+pub fn path<'a>(choice: &'a str, query: &String, folding: Folding) -> Option<Match<'a>> {
+    if query.contains('/') { return segments(choice, query, folding) }
+
+    let start = choice.rfind('/').map_or(0, |slash| slash + 1);
+    match fuzzy(&choice[start..], query, folding) {
+        Some(matching) => {
+            let offset = choice[..start].chars().count();
+            let positions = matching.positions.iter().map(|&position| position + offset).collect();
+            // A file name matched as a whole is scored as a run, so the bonus still tells it apart.
+            let quality = match matching.quality.to_f32() {
+                quality if quality.is_finite() => quality,
+                _ => consecutive(&choice[start..], &matching.positions),
+            };
+            Some(Match::new(Quality(quality + SCORE_MATCH_BASENAME), positions, choice))
+        },
+        None => fuzzy(choice, query, folding),
+    }
+}
+
+// Matches query segments right to left, so the last one prefers the file name.
+fn segments<'a>(choice: &'a str, query: &String, folding: Folding) -> Option<Match<'a>> {
+    let folded = folding.fold(choice);
+    let haystack = &folded.chars;
+    let needles: Vec<Vec<char>> = query.split('/')
+        .filter(|needle| !needle.is_empty())
+        .map(|needle| needle.chars().collect())
+        .collect();
+    let bounds = segment_bounds(haystack);
+
+    let mut positions = Vec::new();
+    let mut remaining = bounds.len();
+    for needle in needles.iter().rev() {
+        loop {
+            if remaining == 0 { return None }
+            remaining -= 1;
+            let (start, end) = bounds[remaining];
+            if haystack[start..end].starts_with(needle) {
+                positions.extend(start..start + needle.len());
+                break;
+            }
+        }
+    }
+    positions.sort();
+
+    let skipped = bounds.len() - needles.len();
+    let mut quality = SCORE_MATCH_CONSECUTIVE * positions.len() as f32 + SCORE_GAP_INNER * skipped as f32;
+    let last = bounds[bounds.len() - 1];
+    if !needles.is_empty() && haystack[last.0..last.1].starts_with(&needles[needles.len() - 1]) {
+        quality += SCORE_MATCH_BASENAME;
+    }
+
+    Some(Match::new(Quality(quality), folded.original_positions(&positions), choice))
+}
+
+fn segment_bounds(haystack: &[char]) -> Vec<(usize, usize)> {
+    let mut bounds = Vec::new();
+    let mut start = 0;
+    for (idx, &character) in haystack.iter().enumerate() {
+        if character == '/' {
+            bounds.push((start, idx));
+            start = idx + 1;
+        }
+    }
+    bounds.push((start, haystack.len()));
+    bounds
+}
+
+fn byte_range(choice: &str, positions: &Vec<usize>) -> Range<usize> {
+    if positions.is_empty() {
+        return Range { start: 0, end: 0 };
+    }
+    let first = positions[0];
+    let last = positions[positions.len() - 1];
+    let mut offsets = choice.char_indices().map(|(offset, _)| offset).chain(Some(choice.len()));
+    let start = offsets.nth(first).unwrap();
+    let end = offsets.nth(last - first).unwrap();
+    Range { start: start, end: end }
+}
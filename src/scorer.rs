@@ -0,0 +1,379 @@
+use score;
+use score::{Match, Quality, Folding, Scheme};
+use query::{Query, Term, Kind};
+use index::Signature;
+use explain::{self, Component};
+use regex;
+use std::fmt::Debug;
+
+// A matching algorithm that `Search` can be configured with.
+// `prepare` is called once whenever the query changes, `score` for every choice,
+// possibly from several threads at once.
+pub trait Scorer: Debug + Send + Sync {
+    fn prepare(&mut self, query: &str, folding: Folding);
+    fn score<'a>(&self, choice: &'a str) -> Option<Match<'a>>;
+
+    // A scorer of the same kind, still to be prepared, to score on another thread.
+    fn fresh(&self) -> Box<dyn Scorer>;
+
+    // Whether every choice matching a query also matched the query without its last character.
+    // When it does, `Search` only rescans the previous results after a character is typed.
+    fn narrows_when_appending(&self) -> bool {
+        true
+    }
+
+    // The highest quality `score` can give the choice, cheaper to find than scoring it.
+    fn upper_bound(&self, _choice: &str) -> f32 {
+        f32::INFINITY
+    }
+
+    // Whether `score` matches the choice, for when its quality does not matter.
+    fn matches(&self, choice: &str) -> bool {
+        self.score(choice).is_some()
+    }
+
+    // The characters every match contains, choices without them are not scored at all.
+    fn signature(&self) -> Signature {
+        Signature::default()
+    }
+
+    // Scores a choice that `score` rejected, forgiving a few typos in the query.
+    // Scorers that have no notion of a typo never match here.
+    fn score_with_typos<'a>(&self, _choice: &'a str) -> Option<Match<'a>> {
+        None
+    }
+
+    // The bonuses and penalties the quality of a match this scorer made adds up from.
+    // Whatever they leave unexplained is reported as `other`.
+    fn explain(&self, _matching: &Match) -> Vec<Component> {
+        Vec::new()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Fuzzy {
+    query: String,
+    folding: Folding,
+}
+
+#[derive(Debug, Default)]
+pub struct Selecta {
+    query: String,
+    folding: Folding,
+}
+
+#[derive(Debug, Default)]
+pub struct Prefix {
+    query: String,
+    folding: Folding,
+}
+
+#[derive(Debug, Default)]
+pub struct Substring {
+    query: String,
+    folding: Folding,
+}
+
+#[derive(Debug)]
+pub struct Extended {
+    query: Query,
+    folding: Folding,
+    scheme: Scheme,
+}
+
+#[derive(Debug, Default)]
+pub struct Regex {
+    pattern: Option<regex::Regex>,
+    is_empty: bool,
+    folding: Folding,
+}
+
+impl Scorer for Fuzzy {
+    fn prepare(&mut self, query: &str, folding: Folding) {
+        self.query = folding.fold_query(query);
+        self.folding = folding;
+    }
+
+    fn fresh(&self) -> Box<dyn Scorer> {
+        Box::new(Fuzzy::default())
+    }
+
+    fn score<'a>(&self, choice: &'a str) -> Option<Match<'a>> {
+        score::fuzzy(choice, &self.query, self.folding)
+    }
+
+    fn upper_bound(&self, choice: &str) -> f32 {
+        score::fuzzy_upper_bound(choice, &self.query)
+    }
+
+    fn matches(&self, choice: &str) -> bool {
+        score::fuzzy_matches(choice, &self.query, self.folding)
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::of(&self.query)
+    }
+
+    fn score_with_typos<'a>(&self, choice: &'a str) -> Option<Match<'a>> {
+        score::tolerant(choice, &self.query, self.folding)
+    }
+
+    fn explain(&self, matching: &Match) -> Vec<Component> {
+        explain::fuzzy(matching.original, &matching.positions)
+    }
+}
+
+impl Scorer for Selecta {
+    fn prepare(&mut self, query: &str, folding: Folding) {
+        self.query = folding.fold_query(query);
+        self.folding = folding;
+    }
+
+    fn fresh(&self) -> Box<dyn Scorer> {
+        Box::new(Selecta::default())
+    }
+
+    fn score<'a>(&self, choice: &'a str) -> Option<Match<'a>> {
+        score::score(choice, &self.query, self.folding)
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::of(&self.query)
+    }
+}
+
+impl Scorer for Prefix {
+    fn prepare(&mut self, query: &str, folding: Folding) {
+        self.query = folding.fold_query(query);
+        self.folding = folding;
+    }
+
+    fn fresh(&self) -> Box<dyn Scorer> {
+        Box::new(Prefix::default())
+    }
+
+    fn score<'a>(&self, choice: &'a str) -> Option<Match<'a>> {
+        score::prefix(choice, &self.query, self.folding)
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::of(&self.query)
+    }
+}
+
+impl Scorer for Substring {
+    fn prepare(&mut self, query: &str, folding: Folding) {
+        self.query = folding.fold_query(query);
+        self.folding = folding;
+    }
+
+    fn fresh(&self) -> Box<dyn Scorer> {
+        Box::new(Substring::default())
+    }
+
+    fn score<'a>(&self, choice: &'a str) -> Option<Match<'a>> {
+        score::substring(choice, &self.query, self.folding)
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::of(&self.query)
+    }
+}
+
+impl Default for Extended {
+    fn default() -> Extended {
+        Extended::with_scheme(Scheme::default())
+    }
+}
+
+// Scores every group of the query and adds up the qualities of the best term in each group.
+impl Scorer for Extended {
+    fn prepare(&mut self, query: &str, folding: Folding) {
+        self.query = Query::parse(&folding.fold_query(query));
+        self.folding = folding;
+    }
+
+    fn fresh(&self) -> Box<dyn Scorer> {
+        Box::new(Extended::with_scheme(self.scheme))
+    }
+
+    fn score<'a>(&self, choice: &'a str) -> Option<Match<'a>> {
+        self.score_groups(choice, false)
+    }
+
+    fn narrows_when_appending(&self) -> bool {
+        self.query.narrows_when_appending()
+    }
+
+    // The sum of the best bound in every group, as `score_groups` adds up their qualities.
+    fn upper_bound(&self, choice: &str) -> f32 {
+        if self.query.is_empty() { return f32::INFINITY }
+
+        self.query.groups.iter().fold(0.0, |sum, group| {
+            sum + group.iter().map(|term| self.term_upper_bound(term, choice)).fold(f32::NEG_INFINITY, f32::max)
+        })
+    }
+
+    fn matches(&self, choice: &str) -> bool {
+        self.query.is_empty() || self.query.groups.iter().all(|group| {
+            group.iter().any(|term| self.term_matches(term, choice))
+        })
+    }
+
+    // A group only requires what all of its terms require, and nothing if one is negated.
+    fn signature(&self) -> Signature {
+        self.query.groups.iter().fold(Signature::default(), |signature, group| {
+            let required = group.iter().map(|term| self.term_signature(term))
+                .fold(None, |common: Option<Signature>, term| {
+                    Some(common.map_or(term, |common| common.intersection(term)))
+                });
+            signature.union(required.unwrap_or_default())
+        })
+    }
+
+    // Only fuzzy terms forgive typos, exact and anchored ones still have to match as typed.
+    fn score_with_typos<'a>(&self, choice: &'a str) -> Option<Match<'a>> {
+        self.score_groups(choice, true)
+    }
+
+    // Every group is broken down by the term that scored best in it. Exact and anchored
+    // terms are scored the way `fuzzy` scores their run, so they break down alike.
+    fn explain(&self, matching: &Match) -> Vec<Component> {
+        let choice = matching.original;
+        self.query.groups.iter().flat_map(|group| {
+            let best = self.best_in_group(group, choice, false).or_else(|| self.best_in_group(group, choice, true));
+            best.map_or(Vec::new(), |best| explain::fuzzy(choice, &best.positions))
+        }).collect()
+    }
+}
+
+impl Extended {
+    pub fn with_scheme(scheme: Scheme) -> Extended {
+        Extended { query: Query::parse(""), folding: Folding::default(), scheme: scheme }
+    }
+
+    fn score_groups<'a>(&self, choice: &'a str, typos: bool) -> Option<Match<'a>> {
+        if self.query.is_empty() { return Some(Match::with_empty_range(choice)) }
+
+        let mut quality = 0.0;
+        let mut positions = Vec::new();
+
+        for group in self.query.groups.iter() {
+            match self.best_in_group(group, choice, typos) {
+                Some(matching) => {
+                    quality += matching.quality.to_f32();
+                    positions.extend(matching.positions);
+                },
+                None => return None,
+            };
+        }
+
+        positions.sort();
+        positions.dedup();
+        Some(Match::new(Quality(quality), positions, choice))
+    }
+
+    // Path segments are split at slashes, so a fuzzy term does not need to contain them.
+    fn term_signature(&self, term: &Term) -> Signature {
+        if term.negated {
+            Signature::default()
+        } else if self.scheme == Scheme::Path && term.kind == Kind::Fuzzy {
+            Signature::of(&term.text.replace("/", ""))
+        } else {
+            Signature::of(&term.text)
+        }
+    }
+
+    // Exact and anchored terms score a run that `fuzzy` could have matched as well, while
+    // file names in the path scheme earn a bonus on top.
+    fn term_upper_bound(&self, term: &Term, choice: &str) -> f32 {
+        match term.kind {
+            _ if term.negated => 0.0,
+            Kind::Fuzzy if self.scheme == Scheme::Path => f32::INFINITY,
+            _ => score::fuzzy_upper_bound(choice, &term.text),
+        }
+    }
+
+    fn term_matches(&self, term: &Term, choice: &str) -> bool {
+        if term.kind == Kind::Fuzzy && self.scheme == Scheme::Default {
+            score::fuzzy_matches(choice, &term.text, self.folding) != term.negated
+        } else {
+            self.score_term(term, choice, false).is_some()
+        }
+    }
+
+    fn best_in_group<'a>(&self, group: &Vec<Term>, choice: &'a str, typos: bool) -> Option<Match<'a>> {
+        let mut best: Option<Match<'a>> = None;
+        for term in group.iter() {
+            match self.score_term(term, choice, typos) {
+                Some(matching) => {
+                    let is_better = best.as_ref().map_or(true, |b| matching.quality.to_f32() > b.quality.to_f32());
+                    if is_better { best = Some(matching) }
+                },
+                None => {},
+            };
+        }
+        best
+    }
+
+    fn score_term<'a>(&self, term: &Term, choice: &'a str, typos: bool) -> Option<Match<'a>> {
+        let folding = self.folding;
+        let fuzzy = match self.scheme {
+            Scheme::Default => score::fuzzy,
+            Scheme::Path => score::path,
+        };
+        let found = match term.kind {
+            Kind::Fuzzy if typos && !term.negated => fuzzy(choice, &term.text, folding)
+                .or_else(|| score::tolerant(choice, &term.text, folding)),
+            Kind::Fuzzy => fuzzy(choice, &term.text, folding),
+            Kind::Exact => score::substring(choice, &term.text, folding),
+            Kind::Prefix => score::prefix(choice, &term.text, folding),
+            Kind::Suffix => score::suffix(choice, &term.text, folding),
+            Kind::Line => score::line(choice, &term.text, folding),
+        };
+        // Exact and anchored terms, and fuzzy ones matching the whole choice, are scored as
+        // the run they match, on the same scale as other fuzzy terms, so they all add up.
+        let found = found.map(|matching| match matching.quality.to_f32() {
+            quality if term.kind == Kind::Fuzzy && quality.is_finite() => matching,
+            _ => Match { quality: Quality(score::consecutive(choice, &matching.positions)), ..matching },
+        });
+
+        if term.negated {
+            match found {
+                Some(_) => None,
+                None => Some(Match::new(Quality(0.0), vec![], choice)),
+            }
+        } else {
+            found
+        }
+    }
+}
+
+impl Scorer for Regex {
+    // A query that is not a valid pattern yet (e.g. an unclosed group while typing) matches nothing.
+    fn prepare(&mut self, query: &str, folding: Folding) {
+        let flags = if folding.case_sensitive { "" } else { "(?i)" };
+        let pattern = Folding { case_sensitive: true, ..folding }.fold_query(query);
+        self.is_empty = query.is_empty();
+        self.pattern = regex::Regex::new(&format!("{}{}", flags, pattern)).ok();
+        self.folding = folding;
+    }
+
+    fn fresh(&self) -> Box<dyn Scorer> {
+        Box::new(Regex::default())
+    }
+
+    // Typing `?` or `*` can widen a pattern again.
+    fn narrows_when_appending(&self) -> bool {
+        false
+    }
+
+    fn score<'a>(&self, choice: &'a str) -> Option<Match<'a>> {
+        if self.is_empty { return Some(Match::with_empty_range(choice)) }
+
+        match self.pattern {
+            Some(ref pattern) => score::pattern(choice, pattern, self.folding),
+            None => None,
+        }
+    }
+}
@@ -6,6 +6,16 @@ use renderer::Renderer;
 use text::Text;
 use std::cmp::min;
 use text::Printable;
+use config::Config;
+use choices::Choices;
+use prompt::Prompt;
+use vi;
+use filter::Worker;
+use std::time::{Duration, Instant};
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+
+const POLL_INTERVAL: i32 = 50;
+const DEBOUNCE: i32 = 30;
 
 pub struct Screen <'a> {
     pub ansi: Ansi<'a>,
@@ -36,19 +46,50 @@ impl <'a> Screen <'a>{
         }
     }
 
-    pub fn handle_keystroke(&self, search: Search<'a>, input: &str) -> Search<'a> {
+    // Edits to the query are filtered later, by `run` or when the search is done.
+    // In vi's normal mode, characters are commands while other keys keep working.
+    pub fn handle_keystroke(&self, search: Search, input: &str) -> Search {
+        if search.is_in_normal_mode() && is_character(input) {
+            return vi::normal(search, input);
+        }
         match input {
            "\u{e}" => search.down(),
            "\u{10}" => search.up(),
-           "\u{7f}" => search.backspace(),
-           "\n" => search.done(),
-            _ => search.append_to_search(input),
+           "\u{7f}" | "\u{8}" => search.edit(Prompt::delete_backwards),
+           "\u{1b}[3~" => search.edit(Prompt::delete_forwards),
+           "\u{1b}[D" | "\u{1b}OD" => search.edit(Prompt::left),
+           "\u{1b}[C" | "\u{1b}OC" => search.edit(Prompt::right),
+           "\u{1}" => search.edit(Prompt::start),
+           "\u{5}" => search.edit(Prompt::end),
+           "\u{1b}b" => search.edit(Prompt::word_left),
+           "\u{1b}f" => search.edit(Prompt::word_right),
+           "\u{17}" => search.edit(Prompt::kill_word_backwards),
+           "\u{15}" => search.edit(Prompt::kill_to_start),
+           "\u{b}" => search.edit(Prompt::kill_to_end),
+           "\u{19}" => search.edit(Prompt::yank),
+           "\u{1b}y" => search.edit(Prompt::yank_previous),
+           "\u{14}" => search.cycle_case(),
+           "\u{2}" => search.scroll_left(),
+           "\u{6}" => search.scroll_right(),
+           "\t" if search.multi => search.toggle_mark(),
+           "\u{1b}[Z" if search.multi => search.toggle_mark_up(),
+           "\u{1b}a" => search.mark_all(),
+           "\u{1b}d" => search.unmark_all(),
+           "\u{1b}[5~" => search.page_up(),
+           "\u{1b}[6~" => search.page_down(),
+           "\u{1b}[H" | "\u{1b}OH" | "\u{1b}[1~" => search.first(),
+           "\u{1b}[F" | "\u{1b}OF" | "\u{1b}[4~" => search.last(),
+           "\n" => search.finish_filtering().done(),
+           "\u{1b}" => vi::escape(search),
+           // Keys without a binding send escape sequences, which are not typed.
+           _ if input.starts_with('\u{1b}') => search,
+           _ => search.edit(|prompt| prompt.insert(input)),
         }
     }
 
     pub fn print(&mut self, search: &Search) {
         let renderer = Renderer;
-        let result = renderer.render(search);
+        let result = renderer.render(search, self.width);
         self.ansi.hide_cursor();
 
         let start_line = self.height - search.visible_limit;
@@ -56,7 +97,7 @@ impl <'a> Screen <'a>{
         for (idx, text) in result.into_iter().enumerate() {
             self.write(start_line + idx, text);
         };
-        self.ansi.set_position(start_line, renderer.header(search).len());
+        self.ansi.set_position(start_line, renderer.cursor_column(search));
         self.ansi.show_cursor();
     }
 
@@ -81,23 +122,90 @@ impl <'a> Screen <'a>{
         self.ansi.set_position(self.height - 1, 0);
     }
 
-    pub fn run_search(&mut self, lines: Vec<String>, initial_query: Option<String>) -> Option<String> {
+    pub fn run_search<C: Into<Choices>>(&mut self, choices: C, config: Config) -> Vec<String> {
+        let (sender, receiver) = channel();
+        drop(sender);
+        self.run(choices.into(), receiver, config)
+    }
+
+    pub fn run_search_streaming(&mut self, lines: Receiver<String>, config: Config) -> Vec<String> {
+        self.run(Choices::new(), lines, config)
+    }
+
+    // Shows the choices while more are still arriving, until the sender hangs up.
+    // The query is filtered on a worker once typing pauses for `DEBOUNCE` milliseconds,
+    // until then the previous results stay on screen. Returns the marked choices, or the selection.
+    fn run(&mut self, choices: Choices, lines: Receiver<String>, config: Config) -> Vec<String> {
         let height = min(20, self.height);
-        let mut search = Search::blank(&lines, initial_query, height);
+        let mut search = Search::configured(choices, config, height).with_loading(true);
+        let mut worker = Worker::new();
+        let mut edited = Instant::now();
 
         self.clear(height);
 
+        let mut changed = true;
         while !search.is_done() {
-            self.print(&search);
+            if search.loading {
+                let (received, arrived) = self.receive(search, &lines);
+                search = received;
+                changed = changed || arrived;
+            }
+            while let Some(filtered) = worker.finished() {
+                search = search.with_filtered(filtered);
+                changed = true;
+            }
+            if search.filtering && edited.elapsed() >= Duration::from_millis(DEBOUNCE as u64) {
+                let filter = search.filter();
+                if !worker.is_running(&filter) { worker.start(filter) }
+            }
+            if changed {
+                self.print(&search);
+                changed = false;
+            }
+
+            let timeout = if search.filtering { DEBOUNCE } else { POLL_INTERVAL };
+            if (search.loading || search.filtering) && !self.ansi.io.wait(timeout) {
+                continue;
+            }
             let input = self.ansi.io.read();
 
             match input {
                 Some(character) => {
                     search = self.handle_keystroke(search, character.as_ref());
+                    edited = Instant::now();
+                    changed = true;
                 },
                 None => break,
             };
         }
-        search.selection()
+        search.selections()
+    }
+
+    // Takes every line that arrived so far, telling whether anything changed.
+    fn receive(&self, search: Search, lines: &Receiver<String>) -> (Search, bool) {
+        let mut batch = Vec::new();
+        let mut loading = true;
+        loop {
+            match lines.try_recv() {
+                Ok(line) => batch.push(line),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    loading = false;
+                    break;
+                },
+            }
+        }
+
+        let changed = !batch.is_empty() || !loading;
+        let search = if batch.is_empty() { search } else { search.append_choices(batch) };
+        (search.with_loading(loading), changed)
+    }
+}
+
+fn is_character(input: &str) -> bool {
+    let mut chars = input.chars();
+    match (chars.next(), chars.next()) {
+        (Some(character), None) => !character.is_control(),
+        _ => false,
     }
 }
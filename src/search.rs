@@ -1,148 +1,635 @@
-use score;
-use score::Match;
-use sorted_result_set::SortedResultSet;
-use std::ascii::AsciiExt;
+use score::{Match, Quality, Case, Folding};
+use scorer::{Scorer, Extended};
+use sorted_result_set::{SortedResultSet, Rank, Tiebreak};
+use explain::Explanation;
+use index::{self, Candidate, Candidates};
+use choices::Choices;
+use marks::{Marks, MarkOrder};
+use prompt::Prompt;
+use vi::{Vi, Mode};
+use config::Config;
+use query::Query;
+
+use filter::{Filter, Filtered, Scoring};
+
+use std::cmp::{min, max};
+use std::str::FromStr;
+use std::sync::atomic::AtomicBool;
+
+const SCROLL_STEP: isize = 8;
+const CACHE_SIZE: usize = 64;
+const SCROLL_OFF: usize = 2;
+
+static NOT_CANCELLED: AtomicBool = AtomicBool::new(false);
+
+// When choices that only match with a typo in the query are shown.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Tolerance {
+    Never,
+    Fallback,
+    Always,
+}
+
+impl Default for Tolerance {
+    fn default() -> Tolerance {
+        Tolerance::Fallback
+    }
+}
+
+impl FromStr for Tolerance {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Tolerance, String> {
+        match name {
+            "never" => Ok(Tolerance::Never),
+            "fallback" => Ok(Tolerance::Fallback),
+            "always" => Ok(Tolerance::Always),
+            _ => Err(format!("unknown typo tolerance '{}', expected never, fallback or always", name)),
+        }
+    }
+}
 
+// A result, by the index of its choice, so that the search owns everything it shows.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hit {
+    pub index: usize,
+    pub quality: Quality,
+    pub positions: Vec<usize>,
+}
+
+impl Hit {
+    pub fn new(index: usize, matching: Match) -> Hit {
+        Hit { index: index, quality: matching.quality, positions: matching.positions }
+    }
+
+    pub fn unscored(index: usize) -> Hit {
+        Hit { index: index, quality: Quality(1.0), positions: vec![] }
+    }
+}
+
+// `current` and `top`, the first result on screen, index the ranked matches. Only the best
+// `depth` of them are ranked, deeper ones once the selection moves towards them.
 #[derive(Debug)]
-pub struct Search<'s> {
+pub struct Search {
     pub query: String,
+    pub prompt: Prompt,
     pub current: usize,
-    pub result: Vec<Match<'s>>,
-    choice_stack: ChoiceStack<'s>,
+    pub top: usize,
+    pub result: Vec<Hit>,
+    depth: usize,
+    results_for: String,
+    pub filtering: bool,
+    epoch: usize,
+    choices: Choices,
+    cache: Cache,
     pub visible_limit: usize,
     done: bool,
+    scorer: Box<dyn Scorer>,
+    pub case: Case,
+    pub ignore_diacritics: bool,
+    pub tolerance: Tolerance,
+    pub tiebreaks: Vec<Tiebreak>,
+    pub scroll: isize,
+    pub loading: bool,
+    pub multi: bool,
+    pub marks: Marks,
+    pub mark_order: MarkOrder,
+    pub vi: Option<Vi>,
 }
 
+// Filtered choices by the query that produced them. However the query was edited,
+// its longest cached prefix narrows down the choices left to score. The least
+// recently used entries are dropped, except for the one holding every choice.
 #[derive(Debug)]
-struct ChoiceStack<'s> {
-    content: Vec<Vec<&'s String>>,
+struct Cache {
+    frames: Vec<Frame>,
 }
 
-impl <'s>ChoiceStack<'s> {
-    pub fn new(input: &'s Vec<String>) -> ChoiceStack<'s> {
-        let initial_choices = input.iter().map(|x| x).collect();
+// The choices matching `query`, and whether choices with typos were let in.
+// Filters running elsewhere share them.
+#[derive(Debug)]
+struct Frame {
+    query: String,
+    choices: Candidates,
+    with_typos: bool,
+}
 
-        ChoiceStack { content: vec![initial_choices] }
-    }
+impl Cache {
+    pub fn new(input: &Choices) -> Cache {
+        let initial_choices = index::candidates(input);
 
-    pub fn push(&mut self, frame: Vec<&'s String>) {
-        self.content.push(frame);
+        Cache { frames: vec![Frame { query: "".to_string(), choices: Candidates::from(initial_choices), with_typos: false }] }
     }
 
-    pub fn pop(&mut self) {
-        if self.content.len() > 1 {
-            self.content.pop();
+    pub fn insert(&mut self, query: String, choices: Candidates, with_typos: bool) {
+        self.frames.retain(|frame| frame.query.is_empty() || frame.query != query);
+        if self.frames.len() == CACHE_SIZE {
+            self.frames.remove(1);
         }
+        self.frames.push(Frame { query: query, choices: choices, with_typos: with_typos });
+    }
+
+    pub fn get(&self, query: &str) -> Option<&Frame> {
+        self.frames.iter().find(|frame| frame.query == query)
+    }
+
+    fn get_mut(&mut self, query: &str) -> Option<&mut Frame> {
+        self.frames.iter_mut().find(|frame| frame.query == query)
+    }
+
+    // Drops every frame but the one for `query` and the one holding every choice.
+    pub fn keep_only(&mut self, query: &str) {
+        self.frames.retain(|frame| frame.query.is_empty() || frame.query == query);
     }
 
-    pub fn peek(&self) -> &Vec<&'s String> {
-        self.content.last().unwrap()
+    // Typo matches of a shorter query may lack choices a longer one lets in, so they are skipped,
+    // as are prefixes whose trailing `$` the query no longer treats as an anchor.
+    pub fn longest_prefix(&self, query: &str) -> &Candidates {
+        let frame = self.frames.iter()
+            .filter(|frame| !frame.with_typos && query.starts_with(&frame.query))
+            .filter(|frame| Query::keeps_anchors(&frame.query, query))
+            .max_by_key(|frame| frame.query.len())
+            .unwrap();
+        &frame.choices
     }
 
-    pub fn last_size(&self) -> usize {
-        self.peek().len()
+    pub fn all(&self) -> &Candidates {
+        &self.frames[0].choices
+    }
+
+    pub fn reset(&mut self) {
+        self.frames.truncate(1);
     }
 }
 
-impl<'s> Search<'s> {
-    pub fn blank(choices: &'s Vec<String>,
-                 initial_search: Option<String>,
-                 visible_limit: usize) -> Search<'s> {
+impl Search {
+    pub fn blank<C: Into<Choices>>(choices: C,
+                                   initial_search: Option<String>,
+                                   visible_limit: usize) -> Search {
+        Search::blank_with_scorer(choices, initial_search, visible_limit, Box::new(Extended::default()))
+    }
+
+    pub fn blank_with_scorer<C: Into<Choices>>(choices: C,
+                                               initial_search: Option<String>,
+                                               visible_limit: usize,
+                                               scorer: Box<dyn Scorer>) -> Search {
         let query = initial_search.unwrap_or("".to_string());
+        let choices = choices.into();
+
+        let cache = Cache::new(&choices);
+
+        let blank = Search { current: 0,
+                 top: 0,
+                 query: "".to_string(),
+                 prompt: Prompt::default(),
+                 result: Vec::new(),
+                 depth: visible_limit,
+                 results_for: "".to_string(),
+                 filtering: false,
+                 epoch: 0,
+                 choices: choices,
+                 cache: cache,
+                 visible_limit: visible_limit,
+                 done: false,
+                 scorer: scorer,
+                 case: Case::default(),
+                 ignore_diacritics: false,
+                 tolerance: Tolerance::default(),
+                 tiebreaks: Vec::new(),
+                 scroll: 0,
+                 loading: false,
+                 multi: false,
+                 marks: Marks::default(),
+                 mark_order: MarkOrder::default(),
+                 vi: None };
+        blank.with_query(&query)
+    }
 
-        let choice_stack = ChoiceStack::new(&choices);
+    pub fn configured<C: Into<Choices>>(choices: C, config: Config, visible_limit: usize) -> Search {
+        let scorer = Box::new(Extended::with_scheme(config.scheme));
+        let blank = Search::blank_with_scorer(choices, None, visible_limit, scorer);
+        let search = Search { case: config.case,
+                              ignore_diacritics: config.ignore_diacritics,
+                              tolerance: config.tolerance,
+                              tiebreaks: config.tiebreaks,
+                              mark_order: config.mark_order,
+                              ..blank };
+        let search = if config.multi { search.with_multi(config.max_marks) } else { search };
+        let search = if config.vi { search.with_vi() } else { search };
+        search.with_query(&config.initial_query.unwrap_or("".to_string()))
+    }
 
-        let result = choices.iter().take(visible_limit).map(|x| Match::with_empty_range(x)).collect();
+    pub fn with_case(self, case: Case) -> Search {
+        Search { case: case, ..self }.refilter()
+    }
 
-        Search::new(query, choice_stack, result, 0, visible_limit, false)
+    pub fn with_diacritics_ignored(self, ignore_diacritics: bool) -> Search {
+        Search { ignore_diacritics: ignore_diacritics, ..self }.refilter()
     }
 
-    fn new(query: String, choice_stack: ChoiceStack<'s>, result: Vec<Match<'s>>, index: usize, visible_limit: usize, done: bool) -> Search<'s> {
-        Search { current: index,
-                 query: query,
-                 result: result,
-                 choice_stack: choice_stack,
-                 visible_limit: visible_limit,
-                 done: done}
+    pub fn with_tolerance(self, tolerance: Tolerance) -> Search {
+        Search { tolerance: tolerance, ..self }.refilter()
+    }
+
+    pub fn with_tiebreaks(self, tiebreaks: Vec<Tiebreak>) -> Search {
+        Search { tiebreaks: tiebreaks, ..self }.refilter()
+    }
+
+    // Lets up to `limit` choices be marked, or any number without one.
+    pub fn with_multi(self, limit: Option<usize>) -> Search {
+        Search { multi: true, marks: Marks::new(limit), ..self }
+    }
+
+    pub fn with_mark_order(self, mark_order: MarkOrder) -> Search {
+        Search { mark_order: mark_order, ..self }
+    }
+
+    // Edits the query with vi keys, starting in insert mode.
+    pub fn with_vi(self) -> Search {
+        Search { vi: Some(Vi::new()), ..self }
+    }
+
+    pub fn is_in_normal_mode(&self) -> bool {
+        self.vi.as_ref().map_or(false, |vi| vi.mode == Mode::Normal)
+    }
+
+    pub fn cycle_case(self) -> Search {
+        let case = self.case.next();
+        self.with_case(case)
+    }
+
+    // Filters all choices again, dropping the results of filters started before.
+    fn refilter(mut self) -> Search {
+        let query = self.query.clone();
+        self.cache.reset();
+        self.epoch += 1;
+        self.with_query(&query)
     }
 
     pub fn is_done(&self) -> bool {
         self.done
     }
 
-    pub fn done(self) -> Search<'s> {
-        Search::new(self.query, self.choice_stack, self.result, self.current, self.visible_limit, true)
+    pub fn done(self) -> Search {
+        Search { done: true, ..self }
     }
 
     pub fn selection(&self) -> Option<String> {
-        self.result.get(self.current).map( |t| t.original.clone())
+        self.result.get(self.current).map(|hit| self.choices[hit.index].to_string())
+    }
+
+    // The marked choices, or the selection while none are marked.
+    pub fn selections(&self) -> Vec<String> {
+        if self.marks.is_empty() {
+            return self.selection().into_iter().collect();
+        }
+        self.marks.in_order(self.mark_order).into_iter().map(|index| self.choices[index].to_string()).collect()
+    }
+
+    // Whether the result at `rank` is marked.
+    pub fn is_marked(&self, rank: usize) -> bool {
+        self.result.get(rank).map_or(false, |hit| self.marks.contains(hit.index))
+    }
+
+    pub fn choices(&self) -> &Choices {
+        &self.choices
+    }
+
+    // The results on screen as matches on their choices, best first.
+    pub fn matches(&self) -> Vec<Match> {
+        self.result.iter().skip(self.top).take(self.visible_limit).map(|hit| self.matching(hit)).collect()
     }
 
-    fn new_for_index(self, index: usize) -> Search<'s> {
-        Search::new(self.query, self.choice_stack, self.result, index,self.visible_limit, self.done)
+    fn matching(&self, hit: &Hit) -> Match {
+        Match::new(hit.quality.clone(), hit.positions.clone(), &self.choices[hit.index])
     }
 
-    pub fn iter_matches<F: FnMut(Match<'s>)>(query: &str, choices: &Vec<&'s String>, mut f: F) {
-        let lower_query = query.to_ascii_lowercase();
+    // Explains the ranking of every ranked result, best first.
+    pub fn explain(&self) -> Vec<Explanation> {
+        let mut scorer = self.scorer.fresh();
+        let query = &self.results_for;
+        scorer.prepare(query, Folding::new(self.case, query, self.ignore_diacritics));
+
+        self.result.iter().map(|hit| {
+            let matching = self.matching(hit);
+            Explanation::new(&matching, scorer.explain(&matching), hit.index, &self.tiebreaks)
+        }).collect()
+    }
+
+    fn new_for_index(self, index: usize) -> Search {
+        let top = self.top;
+        self.new_for_index_from(index, top)
+    }
+
+    // Selects `index`, preferably showing the results from `top` on.
+    fn new_for_index_from(self, index: usize, top: usize) -> Search {
+        let search = self.ranked_to(index + SCROLL_OFF);
+        let top = search.top_for(index, top);
+        Search { current: index, top: top, scroll: 0, ..search }
+    }
+
+    // Moves `top` as little as possible to keep `SCROLL_OFF` results around the selection
+    // on screen, without leaving rows blank below the last result.
+    fn top_for(&self, index: usize, top: usize) -> usize {
+        let margin = min(SCROLL_OFF, self.visible_limit.saturating_sub(1) / 2);
+        let lowest = (index + margin + 1).saturating_sub(self.visible_limit);
+        let highest = index.saturating_sub(margin);
+        let last_top = self.result.len().saturating_sub(self.visible_limit);
+        min(min(max(top, lowest), highest), last_top)
+    }
 
-        for choice in choices.iter() {
-            match score::score(&choice, &lower_query) {
+    // Ranks deeper matches once `index` lies beyond the ranked ones. At least twice as
+    // many are ranked each time, so moving further down rarely ranks them all again.
+    fn ranked_to(mut self, index: usize) -> Search {
+        if index < self.result.len() || self.result.len() >= self.num_matches() { return self }
+
+        let (candidates, with_typos) = match self.cache.get(&self.results_for) {
+            Some(frame) => (frame.choices.clone(), frame.with_typos),
+            None => return self,
+        };
+        self.depth = max(index + 1, 2 * self.depth);
+        let query = self.results_for.clone();
+        let result = if query.is_empty() {
+            candidates.iter().take(self.depth).map(|&candidate| Hit::unscored(candidate as usize)).collect()
+        } else {
+            self.scorer.prepare(&query, Folding::new(self.case, &query, self.ignore_diacritics));
+            self.scoring().shards(&candidates, with_typos).unwrap().0.as_sorted_vec()
+        };
+        Search { result: result, ..self }
+    }
+
+    // Choices lacking a character the scorer requires are skipped without scoring them.
+    pub fn iter_matches<'c, F: FnMut(Candidate, Match<'c>)>(scorer: &dyn Scorer, choices: &'c Choices, candidates: &[Candidate], mut f: F) {
+        let required = scorer.signature();
+        for &candidate in candidates.iter() {
+            let (choice, signature) = choices.entry(candidate as usize).unwrap();
+            if !signature.contains(required) { continue }
+            match scorer.score(choice) {
+                None     => continue,
+                Some(m) => f(candidate, m),
+            };
+        }
+    }
+
+    // A typo can leave out a required character, so every choice is scored here.
+    pub fn iter_typo_matches<'c, F: FnMut(Candidate, Match<'c>)>(scorer: &dyn Scorer, choices: &'c Choices, candidates: &[Candidate], mut f: F) {
+        let required = scorer.signature();
+        for &candidate in candidates.iter() {
+            let (choice, signature) = choices.entry(candidate as usize).unwrap();
+            let strict = if signature.contains(required) { scorer.score(choice) } else { None };
+            match strict.or_else(|| scorer.score_with_typos(choice)) {
                 None     => continue,
-                Some(m) => f(m),
+                Some(m) => f(candidate, m),
             };
         }
     }
 
-    pub fn down(self) -> Search<'s> {
+    pub fn down(self) -> Search {
         let next_index = self.next_index();
         self.new_for_index(next_index)
     }
 
-    pub fn up(self) -> Search<'s> {
+    pub fn up(self) -> Search {
         let next_index = self.prev_index();
         self.new_for_index(next_index)
     }
 
-    pub fn append_to_search(mut self, input: &str) -> Search<'s> {
-        let mut new_query = self.query.clone();
-        new_query.push_str(input.as_ref());
+    // Moves the selection and the results on screen a page down, stopping at the last match.
+    pub fn page_down(self) -> Search {
+        let last = self.num_matches().saturating_sub(1);
+        let index = min(self.current + self.visible_limit, last);
+        let top = self.top + self.visible_limit;
+        self.new_for_index_from(index, top)
+    }
+
+    pub fn page_up(self) -> Search {
+        let index = self.current.saturating_sub(self.visible_limit);
+        let top = self.top.saturating_sub(self.visible_limit);
+        self.new_for_index_from(index, top)
+    }
+
+    // Marks the selection, or unmarks it, and moves on to the next match without looping around.
+    pub fn toggle_mark(self) -> Search {
+        let last = self.num_matches().saturating_sub(1);
+        let next_index = min(self.current + 1, last);
+        self.toggled().new_for_index(next_index)
+    }
+
+    pub fn toggle_mark_up(self) -> Search {
+        let next_index = self.current.saturating_sub(1);
+        self.toggled().new_for_index(next_index)
+    }
+
+    fn toggled(mut self) -> Search {
+        if !self.multi { return self }
+
+        if let Some(index) = self.result.get(self.current).map(|hit| hit.index) {
+            self.marks.toggle(index);
+        }
+        self
+    }
+
+    // Marks every match of the results shown, as long as the limit allows.
+    pub fn mark_all(mut self) -> Search {
+        if !self.multi { return self }
+
+        if let Some(frame) = self.cache.get(&self.results_for) {
+            for &candidate in frame.choices.iter() {
+                if self.marks.is_full() { break }
+                self.marks.mark(candidate as usize);
+            }
+        }
+        self
+    }
+
+    pub fn unmark_all(mut self) -> Search {
+        self.marks.clear();
+        self
+    }
+
+    pub fn first(self) -> Search {
+        self.new_for_index(0)
+    }
+
+    pub fn last(self) -> Search {
+        let last = self.num_matches().saturating_sub(1);
+        self.new_for_index(last)
+    }
+
+    // Scrolls the highlighted choice horizontally, relative to where its match is shown.
+    pub fn scroll_left(self) -> Search {
+        let scroll = max(self.scroll - SCROLL_STEP, -self.highlighted_length());
+        Search { scroll: scroll, ..self }
+    }
+
+    pub fn scroll_right(self) -> Search {
+        let scroll = min(self.scroll + SCROLL_STEP, self.highlighted_length());
+        Search { scroll: scroll, ..self }
+    }
+
+    fn highlighted_length(&self) -> isize {
+        self.result.get(self.current).map_or(0, |hit| self.choices[hit.index].chars().count() as isize)
+    }
+
+    pub fn with_loading(self, loading: bool) -> Search {
+        Search { loading: loading, ..self }
+    }
 
-        let mut result = SortedResultSet::new(self.visible_limit);
-        let mut filtered_choices: Vec<&String> = Vec::new();
-        Search::iter_matches(new_query.as_ref(), &self.choice_stack.peek(),
-                        |matching| {
-                                               let quality = matching.quality.to_f32();
-                                               let choice = matching.original;
-                                               result.push(matching.clone(), quality);
-                                               filtered_choices.push(&choice)
-                                             });
+    // Adds choices that arrived after the search was created. Only the new choices are
+    // filtered, for the query of the results shown, which the matching ones join. Every other
+    // cached query would have to filter them as well, so their frames are dropped instead.
+    pub fn append_choices<I, S>(mut self, choices: I) -> Search where I: IntoIterator<Item=S>, S: AsRef<str> {
+        let offset = self.choices.extend(choices).start;
+        let arrivals = Candidates::from(index::candidates_from(offset, &self.choices));
+        self.cache.frames[0].choices.extend(arrivals.iter().cloned());
+
+        let query = self.results_for.clone();
+        self.cache.keep_only(&query);
+        let with_typos = if query.is_empty() { None } else { self.cache.get(&query).map(|frame| frame.with_typos) };
+        let mut late = None;
+        if let Some(with_typos) = with_typos {
+            self.scorer.prepare(&query, Folding::new(self.case, &query, self.ignore_diacritics));
+            let (result, matched) = self.scoring().shards(&arrivals, with_typos).unwrap();
+            let frame = self.cache.get_mut(&query).unwrap();
+            frame.choices.extend(matched);
+            late = Some(result);
+        }
 
-        self.choice_stack.push(filtered_choices);
+        let result = match late {
+            _ if self.results_for.is_empty() => {
+                let missing = self.depth - min(self.depth, self.result.len());
+                let mut result = self.result.clone();
+                result.extend(arrivals.iter().take(missing).map(|&candidate| Hit::unscored(candidate as usize)));
+                result
+            },
+            Some(late) => {
+                let mut result = SortedResultSet::new(self.depth);
+                for hit in self.result.iter() {
+                    result.push(hit.clone(), Rank::new(&self.matching(hit), hit.index, &self.tiebreaks));
+                }
+                result.merge(late);
+                result.as_sorted_vec()
+            },
+            None => return self.refilter(),
+        };
+        let (current, top) = if self.current < result.len() { (self.current, self.top) } else { (0, 0) };
+        Search { result: result, current: current, top: top, ..self }
+    }
 
-        Search::new(new_query, self.choice_stack, result.as_sorted_vec(), 0, self.visible_limit, self.done)
+    pub fn append_to_search(self, input: &str) -> Search {
+        let mut new_query = self.query.clone();
+        new_query.push_str(input.as_ref());
+        self.with_query(&new_query)
     }
 
-    pub fn backspace(mut self) -> Search<'s> {
+    pub fn backspace(self) -> Search {
         let mut new_query = self.query.clone();
         new_query.pop();
+        self.with_query(&new_query)
+    }
+
+    // Shows the matches for any query, starting from the choices matching its longest cached prefix.
+    pub fn with_query(mut self, query: &str) -> Search {
+        self.prompt.set_text(query);
+        let search = Search { query: query.to_string(), filtering: true, scroll: 0, ..self };
+        let filtered = search.filter().run(&NOT_CANCELLED).unwrap();
+        search.with_filtered(filtered)
+    }
+
+    // Filters for the query right away, unless its results are shown already.
+    pub fn finish_filtering(self) -> Search {
+        if !self.filtering { return self }
+
+        let query = self.query.clone();
+        self.with_query(&query)
+    }
+
+    // Changes the query but keeps showing the previous results until `with_filtered` replaces them.
+    pub fn edit_query(mut self, query: &str) -> Search {
+        self.prompt.set_text(query);
+        let filtering = query != self.results_for;
+        Search { query: query.to_string(), filtering: filtering, scroll: 0, ..self }
+    }
+
+    // Edits the query in the prompt, to be filtered like any other edit.
+    pub fn edit<F>(mut self, edit: F) -> Search where F: FnOnce(&mut Prompt) {
+        edit(&mut self.prompt);
+        if self.prompt.text() == self.query { return self }
+
+        let query = self.prompt.text().to_string();
+        self.edit_query(&query)
+    }
+
+    // What filtering for the current query takes, to run on any thread.
+    pub fn filter(&self) -> Filter {
+        let query = self.query.clone();
+        let mut scorer = self.scorer.fresh();
+        scorer.prepare(&query, Folding::new(self.case, &query, self.ignore_diacritics));
+
+        let (candidates, cached) = match self.cache.get(&query) {
+            Some(frame) => (frame.choices.clone(), Some(frame.with_typos)),
+            None => {
+                // More typos are forgiven as the query grows, so typo matches never narrow.
+                let narrows = scorer.narrows_when_appending() && self.tolerance != Tolerance::Always;
+                let candidates = if narrows { self.cache.longest_prefix(&query) } else { self.cache.all() };
+                (candidates.clone(), None)
+            },
+        };
+
+        Filter {
+            query: query,
+            epoch: self.epoch,
+            scorer: scorer,
+            choices: self.choices.clone(),
+            candidates: candidates,
+            all: self.cache.all().clone(),
+            cached: cached,
+            tiebreaks: self.tiebreaks.clone(),
+            limit: self.visible_limit,
+            tolerance: self.tolerance,
+        }
+    }
+
+    // Shows the results of a filter, unless the settings changed since it started. Choices
+    // that arrived in the meantime are filtered here. Keeps filtering while the query differs.
+    pub fn with_filtered(mut self, filtered: Filtered) -> Search {
+        if !self.filtering || filtered.epoch != self.epoch { return self }
+
+        self.depth = self.visible_limit;
+
+        let filtering = filtered.query != self.query;
+        let query = filtered.query;
+        if query.is_empty() {
+            let result = self.cache.all().iter().take(self.visible_limit).map(|&candidate| Hit::unscored(candidate as usize)).collect();
+            return Search { result: result, results_for: query, filtering: filtering, current: 0, top: 0, ..self }
+        }
 
-        self.choice_stack.pop();
+        let mut result = filtered.result;
+        let mut matched = filtered.matched;
+        if filtered.covered < self.choices.len() {
+            self.scorer.prepare(&query, Folding::new(self.case, &query, self.ignore_diacritics));
+            let arrivals = Candidates::from(index::candidates_from(filtered.covered, &self.choices));
+            let (late, late_matched) = self.scoring().shards(&arrivals, filtered.with_typos).unwrap();
+            result.merge(late);
+            matched.extend(late_matched);
+        }
+        self.cache.insert(query.clone(), matched, filtered.with_typos);
 
-        let mut result = SortedResultSet::new(self.visible_limit);
-        Search::iter_matches(new_query.as_ref(), &self.choice_stack.peek(),
-                             |matching| {
-                                 let quality = matching.quality.to_f32();
-                                 result.push(matching, quality)
-                             } );
+        Search { result: result.as_sorted_vec(), results_for: query, filtering: filtering, current: 0, top: 0, ..self }
+    }
 
-        Search::new(new_query, self.choice_stack, result.as_sorted_vec(), 0, self.visible_limit, self.done)
+    fn scoring(&self) -> Scoring {
+        Scoring {
+            scorer: &*self.scorer,
+            choices: &self.choices,
+            tiebreaks: &self.tiebreaks,
+            limit: self.depth,
+            cancelled: &NOT_CANCELLED,
+        }
     }
 
     fn next_index(&self) -> usize {
-        // TODO: fix this!
-        // current is an index -> zero based
-        // num_matches is a length -> 1 based
-        if self.current + 1 == self.num_matches() {
+        if self.current + 1 >= self.num_matches() {
             0
         } else {
             self.current+1
@@ -160,6 +647,6 @@ impl<'s> Search<'s> {
     }
 
     pub fn num_matches(&self) -> usize {
-        self.choice_stack.last_size()
+        self.cache.get(&self.results_for).map_or(0, |frame| frame.choices.len())
     }
 }
@@ -1,6 +1,109 @@
 use std::collections::BinaryHeap;
 use std::cmp::Ordering;
 use std::clone::Clone;
+use std::str::FromStr;
+use std::mem;
+use std::vec;
+use score::Match;
+
+// Decides the order of choices that matched equally well.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Tiebreak {
+    Length,
+    Begin,
+    End,
+    Index,
+}
+
+impl Tiebreak {
+    pub fn parse_list(names: &str) -> Result<Vec<Tiebreak>, String> {
+        names.split(',').map(|name| name.trim().parse()).collect()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Tiebreak::Length => "length",
+            Tiebreak::Begin => "begin",
+            Tiebreak::End => "end",
+            Tiebreak::Index => "index",
+        }
+    }
+
+    // Smaller keys rank higher.
+    fn key(&self, matching: &Match, index: usize) -> usize {
+        let length = matching.original.chars().count();
+        match *self {
+            Tiebreak::Length => length,
+            Tiebreak::Begin => matching.positions.first().map_or(0, |&first| first),
+            Tiebreak::End => matching.positions.last().map_or(0, |&last| length - last - 1),
+            Tiebreak::Index => index,
+        }
+    }
+}
+
+impl FromStr for Tiebreak {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Tiebreak, String> {
+        match name {
+            "length" => Ok(Tiebreak::Length),
+            "begin" => Ok(Tiebreak::Begin),
+            "end" => Ok(Tiebreak::End),
+            "index" => Ok(Tiebreak::Index),
+            _ => Err(format!("unknown tiebreak '{}', expected length, begin, end or index", name)),
+        }
+    }
+}
+
+// Where a choice ends up in the results: by quality first, then by each tiebreak
+// in turn and finally by its position in the input, so no two choices ever tie.
+#[derive(Clone, Debug)]
+pub struct Rank {
+    pub quality: f32,
+    pub keys: Vec<usize>,
+    pub index: usize,
+}
+
+impl Rank {
+    pub fn new(matching: &Match, index: usize, tiebreaks: &[Tiebreak]) -> Rank {
+        let keys = tiebreaks.iter().map(|tiebreak| tiebreak.key(matching, index)).collect();
+        Rank { quality: matching.quality.to_f32(), keys: keys, index: index }
+    }
+}
+
+impl Ord for Rank {
+    // Greater ranks are better ones.
+    fn cmp(&self, other: &Rank) -> Ordering {
+        compare_quality(self.quality, other.quality)
+            .then_with(|| other.keys.cmp(&self.keys))
+            .then_with(|| other.index.cmp(&self.index))
+    }
+}
+
+impl PartialOrd for Rank {
+    fn partial_cmp(&self, other: &Rank) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Eq for Rank {
+}
+
+impl PartialEq for Rank {
+    fn eq(&self, other: &Rank) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+// A quality that is not a number ranks below every other one.
+fn compare_quality(quality: f32, other: f32) -> Ordering {
+    match (quality.is_nan(), other.is_nan()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        (false, false) => quality.partial_cmp(&other).unwrap(),
+    }
+}
 
 pub struct SortedResultSet<T:Clone> {
     results: BinaryHeap<ScoreResult<T>>,
@@ -14,8 +117,8 @@ impl<T:Clone> SortedResultSet<T> {
             size:     size}
     }
 
-    pub fn push(&mut self, choice: T, quality: f32) {
-        let result = ScoreResult { quality: quality, choice: choice};
+    pub fn push(&mut self, choice: T, rank: Rank) {
+        let result = ScoreResult { rank: rank, choice: choice};
 
         if self.is_full() {
             self.push_pop(result);
@@ -24,30 +127,73 @@ impl<T:Clone> SortedResultSet<T> {
         }
     }
 
-    fn is_full(&self) -> bool {
+    pub fn is_full(&self) -> bool {
         self.results.len() >= self.size
     }
 
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    // The lowest rank that is still kept, anything ranking below it is dropped once full.
+    pub fn worst(&self) -> Option<&Rank> {
+        self.results.peek().map(|result| &result.rank)
+    }
+
+    // Keeps the best of both, starting from the larger set so fewer results are pushed again.
+    pub fn merge(&mut self, mut other: SortedResultSet<T>) {
+        if other.results.len() > self.results.len() {
+            mem::swap(&mut self.results, &mut other.results);
+            while self.results.len() > self.size {
+                self.results.pop();
+            }
+        }
+        for result in other.results.into_iter() {
+            self.push(result.choice, result.rank);
+        }
+    }
+
+    pub fn merge_all<I: IntoIterator<Item=SortedResultSet<T>>>(size: usize, sets: I) -> SortedResultSet<T> {
+        sets.into_iter().fold(SortedResultSet::new(size), |mut merged, set| {
+            merged.merge(set);
+            merged
+        })
+    }
+
+    // Best first, without giving up the results. Only the retained results are sorted.
+    pub fn iter(&self) -> vec::IntoIter<&ScoreResult<T>> {
+        let mut results: Vec<&ScoreResult<T>> = self.results.iter().collect();
+        results.sort();
+        results.into_iter()
+    }
+
     pub fn as_sorted_vec(self) -> Vec<T> {
-        self.results.into_sorted_vec().iter().map(|score_result| score_result.choice.clone()).collect()
+        self.results.into_sorted_vec().into_iter().map(|score_result| score_result.choice).collect()
     }
 
+    // Only replaces the worst result when the new one ranks higher.
     fn push_pop(&mut self, result: ScoreResult<T>) {
-        self.results.pop();
-        self.results.push(result);
+        let is_better = self.results.peek().map_or(false, |worst| result < *worst);
+        if is_better {
+            self.results.pop();
+            self.results.push(result);
+        }
     }
 }
 
-
 pub struct ScoreResult<T> {
-    pub quality: f32,
+    pub rank: Rank,
     pub choice: T,
 }
 
 impl<T> Ord for ScoreResult<T> {
     fn cmp(&self, other: &ScoreResult<T>) -> Ordering {
         // Reverses ordering to make the binary max heap a min heap in Search::filter.
-        self.quality.partial_cmp(&other.quality).unwrap_or(Ordering::Equal).reverse()
+        self.rank.cmp(&other.rank).reverse()
     }
 }
 
@@ -62,6 +208,6 @@ impl<T> Eq for ScoreResult<T> {
 
 impl<T> PartialEq for ScoreResult<T> {
     fn eq(&self, other: &ScoreResult<T>) -> bool {
-       self.quality == other.quality
+       self.rank == other.rank
     }
 }
@@ -1,13 +1,26 @@
 use score::Match;
 use ansi::Ansi;
 use ansi_term::Colour::Blue;
+use width::char_width;
+
+pub const ELLIPSIS: &'static str = "..";
 
 #[derive(PartialEq, Debug)]
 pub enum Text<'a> {
-    Colored(Match<'a>),
+    Colored(Match<'a>, Window),
     Normal(String),
-    Highlight(String),
+    Highlight(Match<'a>, Window),
     Blank,
+    // Printed in front of a choice, to show whether it is marked.
+    Gutter(&'static str, Box<Text<'a>>),
+}
+
+// The part of a choice that fits on screen: characters before `start` are scrolled out of view
+// and `width` is the number of columns available, including any ellipsis.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Window {
+    pub start: usize,
+    pub width: usize,
 }
 
 pub trait Printable {
@@ -17,18 +30,91 @@ pub trait Printable {
 impl <'a> Printable for Text<'a> {
     fn print(self, ansi: &mut Ansi) {
         match self {
-            Text::Colored(ref matching) => {
-                let (start, middle, end) = matching.parts();
-                let text = format!("{}{}{}", start, Blue.paint(middle.as_ref()), end);
+            Text::Colored(ref matching, window) => {
+                let text = paint_matches(matching, window, "");
                 ansi.print(&text);
             }
             Text::Normal(ref text) => {
                 ansi.print(&text);
             }
-            Text::Highlight(ref text) => {
+            Text::Highlight(ref matching, window) => {
+                // Painting a match resets all attributes, so the inversion has to be turned back on.
+                let text = paint_matches(matching, window, &Ansi::esc("7m"));
                 ansi.inverted(&text);
             }
             Text::Blank => ansi.print(""),
+            Text::Gutter(gutter, text) => {
+                ansi.print(gutter);
+                text.print(ansi);
+            }
         };
     }
 }
+
+fn paint_matches(matching: &Match, window: Window, after_match: &str) -> String {
+    let chars: Vec<char> = matching.original.chars().collect();
+    let mut text = String::new();
+    let mut column = 0;
+
+    if window.start > 0 {
+        text.push_str(ELLIPSIS);
+        column += ELLIPSIS.len();
+    }
+
+    let limit = if fits(&chars[window.start..], column, window.width) {
+        window.width
+    } else {
+        window.width.saturating_sub(ELLIPSIS.len())
+    };
+
+    let mut run = String::new();
+    let mut run_is_matched = false;
+    let mut clipped = false;
+
+    for idx in window.start..chars.len() {
+        let character = chars[idx];
+        let width = char_width(character, column);
+        if column + width > limit {
+            clipped = true;
+            break;
+        }
+
+        let is_matched = matching.is_matched(idx);
+        if is_matched != run_is_matched {
+            push_run(&mut text, &run, run_is_matched, after_match);
+            run.clear();
+            run_is_matched = is_matched;
+        }
+        if character == '\t' {
+            for _ in 0..width { run.push(' ') }
+        } else {
+            run.push(character);
+        }
+        column += width;
+    }
+    push_run(&mut text, &run, run_is_matched, after_match);
+
+    if clipped {
+        text.push_str(ELLIPSIS);
+    }
+    text
+}
+
+fn fits(chars: &[char], start_column: usize, width: usize) -> bool {
+    let mut column = start_column;
+    for &character in chars.iter() {
+        column += char_width(character, column);
+    }
+    column <= width
+}
+
+fn push_run(text: &mut String, run: &str, is_matched: bool, after_match: &str) {
+    if run.is_empty() {
+        return;
+    }
+    if is_matched {
+        text.push_str(&format!("{}{}", Blue.paint(run), after_match));
+    } else {
+        text.push_str(run);
+    }
+}
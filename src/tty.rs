@@ -4,10 +4,12 @@ use std::process::Command;
 use std::process::Stdio;
 use std::path::Path;
 use std::os::unix::prelude::AsRawFd;
-use libc::{c_ushort, c_int, c_ulong};
-use std::str;
-use std::cmp::min;
+use libc::{c_ushort, c_int, c_ulong, poll, pollfd, POLLIN};
+use width;
 
+const ESCAPE: u8 = 0x1b;
+// How long the rest of an escape sequence may take to arrive after the escape.
+const ESCAPE_TIMEOUT: i32 = 10;
 
 pub struct TTY {
     file: File,
@@ -22,6 +24,8 @@ pub trait IO {
     fn lines(&self) -> Vec<String>;
     fn dimensions(&self) -> (usize, usize);
     fn reset(&self);
+    // Waits at most `milliseconds` for input, telling whether there is some to read.
+    fn wait(&mut self, milliseconds: i32) -> bool;
 }
 
 impl IO for TTY {
@@ -34,15 +38,23 @@ impl IO for TTY {
         };
     }
 
+    // Reads a key: a whole character, or an escape sequence when the key sends one.
+    // Keys that are not valid UTF-8 are dropped, only the end of input returns `None`.
     fn read(&mut self) -> Option<String> {
-        let mut buffer = [0] ;
-        let res = match self.file.read(&mut buffer) {
-            Ok(c) if  c > 0 => {
-                str::from_utf8(&buffer).ok().map(|x| x.to_string())
-            },
-            _ => None,
-        };
-        res
+        loop {
+            let mut key = Vec::new();
+            self.read_char(&mut key)?;
+            if key[0] == ESCAPE && self.wait(ESCAPE_TIMEOUT) {
+                self.read_char(&mut key)?;
+                if key[1] == b'[' || key[1] == b'O' {
+                    while let Some(byte) = self.read_byte() {
+                        key.push(byte);
+                        if byte >= 0x40 && byte <= 0x7e { break }
+                    }
+                }
+            }
+            if let Ok(key) = String::from_utf8(key) { return Some(key) }
+        }
     }
 
     fn last(&self) -> &str {
@@ -62,6 +74,21 @@ impl IO for TTY {
     fn reset(&self) {
         TTY::stty(&self.file, &[self.original_state.as_ref()]);
     }
+
+    fn wait(&mut self, milliseconds: i32) -> bool {
+        let mut descriptor = pollfd { fd: self.file.as_raw_fd(), events: POLLIN, revents: 0 };
+        unsafe { poll(&mut descriptor, 1, milliseconds) > 0 }
+    }
+}
+
+// How many more bytes a character starting with `byte` takes in UTF-8.
+fn continuation_bytes(byte: u8) -> usize {
+    match byte {
+        0xc0..=0xdf => 1,
+        0xe0..=0xef => 2,
+        0xf0..=0xf7 => 3,
+        _ => 0,
+    }
 }
 
 impl TTY {
@@ -80,9 +107,26 @@ impl TTY {
         }
     }
 
+    // Adds a character to `key`, with as many bytes as its first one announces.
+    fn read_char(&mut self, key: &mut Vec<u8>) -> Option<()> {
+        let first = self.read_byte()?;
+        key.push(first);
+        for _ in 0..continuation_bytes(first) {
+            key.push(self.read_byte()?);
+        }
+        Some(())
+    }
+
+    fn read_byte(&mut self) -> Option<u8> {
+        let mut buffer = [0];
+        match self.file.read(&mut buffer) {
+            Ok(c) if c > 0 => Some(buffer[0]),
+            _ => None,
+        }
+    }
+
     fn trim(&self, line: &str) -> String {
-        let actual = min(line.len(), self.dimensions.0);
-        line[..actual].into()
+        width::truncate(line, self.dimensions.0)
     }
 
     fn get_window_size(file: &File) -> (usize, usize) {
@@ -111,16 +155,15 @@ impl TTY {
         }
     }
 
+    // Hands stty its own handle to the terminal, so stdin stays free for the choices.
     fn stty(file: &File, args: &[&str]) -> Option<String> {
-        extern { fn dup2(src: c_int, dst: c_int) -> c_int;  }
-        unsafe {
-            // This is a hack until a replacement for InheritFd from old_io is available.
-            let raw_fd = file.as_raw_fd();
-            dup2(raw_fd, 0);
-            match Command::new("stty").args(args).stdin(Stdio::inherit()).output() {
-                Err(k) => { panic!(k.to_string()) }
-                Ok(output) => { String::from_utf8(output.stdout).ok() }
-            }
+        let terminal = match file.try_clone() {
+            Ok(terminal) => terminal,
+            Err(e) => { panic!(e.to_string()) }
+        };
+        match Command::new("stty").args(args).stdin(Stdio::from(terminal)).output() {
+            Err(k) => { panic!(k.to_string()) }
+            Ok(output) => { String::from_utf8(output.stdout).ok() }
         }
     }
 
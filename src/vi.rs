@@ -0,0 +1,390 @@
+use search::Search;
+use prompt::Prompt;
+
+use std::cmp::{min, max};
+
+// Counts are capped at this, so a long run of digits cannot repeat putting for ages.
+const MAX_COUNT: usize = 9999;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    Insert,
+    Normal,
+}
+
+impl Mode {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Mode::Insert => "insert",
+            Mode::Normal => "normal",
+        }
+    }
+}
+
+// Modal editing of the query. In normal mode, keys are collected in `pending`
+// until they make up a whole command, like `2dw`.
+#[derive(Clone, Debug)]
+pub struct Vi {
+    pub mode: Mode,
+    pending: String,
+}
+
+impl Vi {
+    pub fn new() -> Vi {
+        Vi { mode: Mode::Insert, pending: String::new() }
+    }
+
+    fn push(&mut self, key: &str) -> Option<Command> {
+        self.pending.push_str(key);
+        let keys: Vec<char> = self.pending.chars().collect();
+        match parse(&keys) {
+            Parse::Incomplete => None,
+            Parse::Invalid => {
+                self.pending.clear();
+                None
+            },
+            Parse::Complete(command) => {
+                self.pending.clear();
+                Some(command)
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Motion {
+    Left,
+    Right,
+    Start,
+    FirstNonBlank,
+    End,
+    WordForward(bool),
+    WordBackward(bool),
+    WordEnd(bool),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operator {
+    Delete,
+    Change,
+    Yank,
+}
+
+// What an operator works on. `Object(inner, big)` is a word, with its surrounding
+// whitespace unless `inner`, made of any characters but whitespace if `big`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Target {
+    Motion(Motion),
+    Line,
+    Object(bool, bool),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    Move(Motion),
+    Operate(Operator, Target),
+    // Where to insert, if not at the cursor.
+    Insert(Option<Motion>),
+    Put(bool),
+    Down,
+    Up,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Command {
+    pub count: usize,
+    pub action: Action,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Parse {
+    Complete(Command),
+    Incomplete,
+    Invalid,
+}
+
+// Enter normal mode, or forget the keys of an unfinished command. Like in vim,
+// the cursor moves back onto the last character typed.
+pub fn escape(search: Search) -> Search {
+    let mut search = search;
+    match search.vi {
+        Some(ref mut vi) if vi.mode == Mode::Insert => vi.mode = Mode::Normal,
+        Some(ref mut vi) => {
+            vi.pending.clear();
+            return search;
+        },
+        None => return search,
+    }
+    search.edit(|prompt| prompt.left())
+}
+
+// Handles a key typed in normal mode.
+pub fn normal(search: Search, key: &str) -> Search {
+    let mut search = search;
+    let command = match search.vi.as_mut().and_then(|vi| vi.push(key)) {
+        Some(command) => command,
+        None => return search,
+    };
+    let count = command.count;
+
+    match command.action {
+        // Moving through every match wraps around to where it started.
+        Action::Down => (0..count % max(search.num_matches(), 1)).fold(search, |search, _| search.down()),
+        Action::Up => (0..count % max(search.num_matches(), 1)).fold(search, |search, _| search.up()),
+        Action::Move(motion) => search.edit(|prompt| {
+            let target = repeat(prompt, motion, count);
+            prompt.move_to(target);
+            stay_on_text(prompt);
+        }),
+        Action::Insert(motion) => {
+            let search = search.edit(|prompt| {
+                if let Some(motion) = motion {
+                    let target = find(&chars(prompt), prompt.cursor(), motion);
+                    prompt.move_to(target);
+                }
+            });
+            insert_mode(search)
+        },
+        // Leaves the cursor on the last character put, if there was anything to put.
+        Action::Put(after) => search.edit(|prompt| {
+            let (length, cursor) = (prompt.len(), prompt.cursor());
+            if after && length > 0 { prompt.right() }
+            for _ in 0..count { prompt.yank() }
+            if prompt.len() == length { prompt.move_to(cursor) } else { prompt.left() }
+        }),
+        Action::Operate(operator, target) => {
+            let search = search.edit(|prompt| {
+                let (start, end) = range(prompt, operator, target, count);
+                match operator {
+                    Operator::Yank => prompt.copy(start, end),
+                    _ => prompt.kill(start, end),
+                }
+                prompt.move_to(start);
+                if operator != Operator::Change { stay_on_text(prompt) }
+            });
+            if operator == Operator::Change { insert_mode(search) } else { search }
+        },
+    }
+}
+
+fn insert_mode(mut search: Search) -> Search {
+    if let Some(ref mut vi) = search.vi {
+        vi.mode = Mode::Insert;
+    }
+    search
+}
+
+// In normal mode the cursor is on a character, never after the last one.
+fn stay_on_text(prompt: &mut Prompt) {
+    let last = prompt.len().saturating_sub(1);
+    let cursor = min(prompt.cursor(), last);
+    prompt.move_to(cursor);
+}
+
+// A count, then either a motion, a command of its own or an operator with a count and
+// a target. Both counts multiply, so `2d3w` deletes six words.
+pub fn parse(keys: &[char]) -> Parse {
+    let (count, rest) = take_count(keys);
+    let key = match rest.first() {
+        Some(&key) => key,
+        None => return Parse::Incomplete,
+    };
+    if rest.len() > 1 && !is_operator(key) {
+        return Parse::Invalid;
+    }
+
+    let action = match key {
+        'd' | 'c' | 'y' => {
+            let operator = match key { 'd' => Operator::Delete, 'c' => Operator::Change, _ => Operator::Yank };
+            let (inner, rest) = take_count(&rest[1..]);
+            let target = match parse_target(key, rest) {
+                Ok(target) => target,
+                Err(parse) => return parse,
+            };
+            return Parse::Complete(Command { count: min(count.saturating_mul(inner), MAX_COUNT), action: Action::Operate(operator, target) });
+        },
+        'x' => Action::Operate(Operator::Delete, Target::Motion(Motion::Right)),
+        'X' => Action::Operate(Operator::Delete, Target::Motion(Motion::Left)),
+        'D' => Action::Operate(Operator::Delete, Target::Motion(Motion::End)),
+        'C' => Action::Operate(Operator::Change, Target::Motion(Motion::End)),
+        's' => Action::Operate(Operator::Change, Target::Motion(Motion::Right)),
+        'S' => Action::Operate(Operator::Change, Target::Line),
+        'i' => Action::Insert(None),
+        'a' => Action::Insert(Some(Motion::Right)),
+        'I' => Action::Insert(Some(Motion::FirstNonBlank)),
+        'A' => Action::Insert(Some(Motion::End)),
+        'p' => Action::Put(true),
+        'P' => Action::Put(false),
+        'j' => Action::Down,
+        'k' => Action::Up,
+        _ => match motion(key) {
+            Some(motion) => Action::Move(motion),
+            None => return Parse::Invalid,
+        },
+    };
+    Parse::Complete(Command { count: count, action: action })
+}
+
+fn parse_target(operator: char, keys: &[char]) -> Result<Target, Parse> {
+    match (keys.first(), keys.get(1), keys.len()) {
+        (None, _, _) => Err(Parse::Incomplete),
+        (Some(&key), _, 1) if key == operator => Ok(Target::Line),
+        (Some(&'i'), None, _) | (Some(&'a'), None, _) => Err(Parse::Incomplete),
+        (Some(&kind), Some(&word), 2) if (kind == 'i' || kind == 'a') && (word == 'w' || word == 'W') => {
+            Ok(Target::Object(kind == 'i', word == 'W'))
+        },
+        (Some(&key), _, 1) => motion(key).map(Target::Motion).ok_or(Parse::Invalid),
+        _ => Err(Parse::Invalid),
+    }
+}
+
+fn is_operator(key: char) -> bool {
+    key == 'd' || key == 'c' || key == 'y'
+}
+
+// A count starts with any digit but 0, which moves to the start instead.
+fn take_count(keys: &[char]) -> (usize, &[char]) {
+    let digits = match keys.first() {
+        Some(&'0') => 0,
+        _ => keys.iter().take_while(|key| key.is_ascii_digit()).count(),
+    };
+    let count = keys[..digits].iter().fold(0usize, |count, key| {
+        count.saturating_mul(10).saturating_add(key.to_digit(10).unwrap() as usize)
+    });
+    (min(max(count, 1), MAX_COUNT), &keys[digits..])
+}
+
+fn motion(key: char) -> Option<Motion> {
+    match key {
+        'h' => Some(Motion::Left),
+        'l' => Some(Motion::Right),
+        '0' => Some(Motion::Start),
+        '^' => Some(Motion::FirstNonBlank),
+        '$' => Some(Motion::End),
+        'w' => Some(Motion::WordForward(false)),
+        'W' => Some(Motion::WordForward(true)),
+        'b' => Some(Motion::WordBackward(false)),
+        'B' => Some(Motion::WordBackward(true)),
+        'e' => Some(Motion::WordEnd(false)),
+        'E' => Some(Motion::WordEnd(true)),
+        _ => None,
+    }
+}
+
+// The characters from `start` up to `end` an operator works on.
+fn range(prompt: &Prompt, operator: Operator, target: Target, count: usize) -> (usize, usize) {
+    let chars = chars(prompt);
+    let cursor = prompt.cursor();
+    match target {
+        Target::Line => (0, chars.len()),
+        Target::Object(inner, big) => object(&chars, cursor, inner, big, count),
+        // Changing words leaves the whitespace after them, as in vim. The word under
+        // the cursor counts as the first one, even on its last character.
+        Target::Motion(Motion::WordForward(big)) if operator == Operator::Change && !is_blank(&chars, cursor) => {
+            let at_end = chars.get(cursor + 1).map_or(true, |&next| class(next, big) != class(chars[cursor], big));
+            let moves = min(if at_end { count - 1 } else { count }, chars.len());
+            let end = (0..moves).fold(cursor, |position, _| find(&chars, position, Motion::WordEnd(big)));
+            (cursor, min(end + 1, chars.len()))
+        },
+        Target::Motion(motion) => {
+            let target = repeat(prompt, motion, count);
+            let inclusive = match motion { Motion::WordEnd(_) => 1, _ => 0 };
+            (min(cursor, target), min(max(cursor, target + inclusive), chars.len()))
+        },
+    }
+}
+
+// Every motion moves a character at least or not at all, so no more than one
+// move per character is repeated.
+fn repeat(prompt: &Prompt, motion: Motion, count: usize) -> usize {
+    let chars = chars(prompt);
+    (0..min(count, chars.len())).fold(prompt.cursor(), |position, _| find(&chars, position, motion))
+}
+
+// Where `motion` leads from `position`. The end of the text is a position too.
+fn find(chars: &[char], position: usize, motion: Motion) -> usize {
+    let len = chars.len();
+    match motion {
+        Motion::Left => position.saturating_sub(1),
+        Motion::Right => min(position + 1, len),
+        Motion::Start => 0,
+        Motion::FirstNonBlank => chars.iter().position(|c| !c.is_whitespace()).unwrap_or(len),
+        Motion::End => len,
+        Motion::WordForward(big) => {
+            let mut next = position;
+            if next < len && !chars[next].is_whitespace() {
+                let kind = class(chars[next], big);
+                while next < len && class(chars[next], big) == kind { next += 1 }
+            }
+            while next < len && chars[next].is_whitespace() { next += 1 }
+            next
+        },
+        Motion::WordBackward(big) => {
+            let mut previous = position;
+            while previous > 0 && chars[previous - 1].is_whitespace() { previous -= 1 }
+            if previous > 0 {
+                let kind = class(chars[previous - 1], big);
+                while previous > 0 && class(chars[previous - 1], big) == kind { previous -= 1 }
+            }
+            previous
+        },
+        Motion::WordEnd(big) => {
+            let mut next = position + 1;
+            while next < len && chars[next].is_whitespace() { next += 1 }
+            if next >= len { return len.saturating_sub(1) }
+            let kind = class(chars[next], big);
+            while next + 1 < len && class(chars[next + 1], big) == kind { next += 1 }
+            next
+        },
+    }
+}
+
+// `count` words from the one under the cursor, whitespace counting as a word of its own.
+// An outer object also takes the whitespace after a word, or the word after whitespace.
+// Without whitespace after it, it takes the whitespace before the first word instead.
+fn object(chars: &[char], cursor: usize, inner: bool, big: bool, count: usize) -> (usize, usize) {
+    let len = chars.len();
+    if len == 0 { return (0, 0) }
+
+    let cursor = min(cursor, len - 1);
+    let kind = |position: usize| class(chars[position], big);
+    let run_end = |position: usize| (position..len).find(|&next| kind(next) != kind(position)).unwrap_or(len);
+
+    let mut start = cursor;
+    while start > 0 && kind(start - 1) == kind(cursor) { start -= 1 }
+
+    let mut end = start;
+    for _ in 0..count {
+        if end == len { break }
+        let blank = kind(end) == 0;
+        end = run_end(end);
+        if !inner && end < len && (blank || kind(end) == 0) {
+            end = run_end(end);
+        }
+    }
+
+    if !inner && kind(cursor) != 0 && kind(end - 1) != 0 {
+        while start > 0 && kind(start - 1) == 0 { start -= 1 }
+    }
+    (start, end)
+}
+
+// Whitespace, letters, digits and underscores, or anything else. A big word
+// lumps everything but whitespace together.
+fn class(character: char, big: bool) -> u8 {
+    if character.is_whitespace() {
+        0
+    } else if big || character.is_alphanumeric() || character == '_' {
+        1
+    } else {
+        2
+    }
+}
+
+fn is_blank(chars: &[char], position: usize) -> bool {
+    chars.get(position).map_or(true, |c| c.is_whitespace())
+}
+
+fn chars(prompt: &Prompt) -> Vec<char> {
+    prompt.text().chars().collect()
+}
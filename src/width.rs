@@ -0,0 +1,78 @@
+use unicode_width::UnicodeWidthChar;
+use std::iter::Peekable;
+use std::str::Chars;
+
+pub const TAB_WIDTH: usize = 8;
+
+// The number of terminal columns `text` takes up.
+// Wide characters take two columns, combining marks and ANSI escape sequences none.
+pub fn display_width(text: &str) -> usize {
+    let mut column = 0;
+    let mut chars = text.chars().peekable();
+
+    while let Some(character) = chars.next() {
+        if character == '\x1b' {
+            take_escape(&mut chars);
+        } else {
+            column += char_width(character, column);
+        }
+    }
+    column
+}
+
+// Cuts `text` down to `columns` without splitting a wide character.
+// Tabs are expanded and escape sequences are kept, so colors are still reset after the cut.
+pub fn truncate(text: &str, columns: usize) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut column = 0;
+    let mut clipped = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(character) = chars.next() {
+        if character == '\x1b' {
+            result.push(character);
+            result.push_str(&take_escape(&mut chars));
+            continue;
+        }
+
+        let width = char_width(character, column);
+        if clipped || column + width > columns {
+            clipped = true;
+            continue;
+        }
+
+        if character == '\t' {
+            for _ in 0..width { result.push(' ') }
+        } else {
+            result.push(character);
+        }
+        column += width;
+    }
+    result
+}
+
+// The width of `character` when it is printed at `column`.
+pub fn char_width(character: char, column: usize) -> usize {
+    if character == '\t' {
+        TAB_WIDTH - column % TAB_WIDTH
+    } else {
+        character.width().unwrap_or(0)
+    }
+}
+
+// Consumes the rest of an escape sequence after the ESC character.
+fn take_escape(chars: &mut Peekable<Chars>) -> String {
+    let mut sequence = String::new();
+    match chars.next() {
+        Some('[') => {
+            sequence.push('[');
+            while let Some(character) = chars.next() {
+                sequence.push(character);
+                if character >= '\x40' && character <= '\x7e' { break }
+            }
+        },
+        Some(character) => sequence.push(character),
+        None => {},
+    };
+    sequence
+}
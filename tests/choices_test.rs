@@ -0,0 +1,60 @@
+extern crate icepick;
+
+#[cfg(test)]
+mod tests {
+    use icepick::choices::Choices;
+    use icepick::index::Signature;
+    use std::env;
+    use std::fs::File;
+    use std::io::Write;
+
+    #[test]
+    fn looks_up_choices_by_index() {
+        let choices = Choices::from(vec!["one".to_string(), "".to_string(), "three".to_string()]);
+
+        assert_eq!(choices.len(), 3);
+        assert_eq!(&choices[0], "one");
+        assert_eq!(&choices[1], "");
+        assert_eq!(&choices[2], "three");
+        assert_eq!(choices.get(3), None);
+    }
+
+    #[test]
+    fn tells_which_indices_were_appended() {
+        let mut choices = Choices::new();
+        assert!(choices.is_empty());
+
+        assert_eq!(choices.extend(vec!["one", "two"]), 0..2);
+        assert_eq!(choices.extend(Vec::<String>::new()), 2..2);
+        assert_eq!(choices.extend(vec!["three"]), 2..3);
+        assert_eq!(choices.iter().collect::<Vec<&str>>(), vec!["one", "two", "three"]);
+    }
+
+    #[test]
+    fn clones_keep_their_choices_while_the_original_grows() {
+        let mut choices = Choices::from(vec!["one".to_string()]);
+        let snapshot = choices.clone();
+
+        choices.extend(vec!["two", "three"]);
+        choices.extend(Vec::<String>::new());
+        choices.extend(vec!["four"]);
+
+        assert_eq!(snapshot.len(), 1);
+        assert_eq!(snapshot.get(1), None);
+        assert_eq!(choices.iter().collect::<Vec<&str>>(), vec!["one", "two", "three", "four"]);
+    }
+
+    #[test]
+    fn maps_the_trimmed_lines_of_a_file() {
+        let path = env::temp_dir().join("icepick_choices_test.txt");
+        File::create(&path).unwrap().write_all(b"  one\r\n\ntwo \nthree").unwrap();
+
+        let choices = Choices::map(&File::open(&path).unwrap()).unwrap();
+        assert_eq!(choices.iter().collect::<Vec<&str>>(), vec!["one", "", "two", "three"]);
+        assert_eq!(choices.entry(2).map(|(_, signature)| signature), Some(Signature::of("two")));
+
+        let mut choices = choices;
+        choices.extend(vec!["four"]);
+        assert_eq!(&choices[4], "four");
+    }
+}
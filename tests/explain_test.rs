@@ -0,0 +1,73 @@
+extern crate icepick;
+
+#[cfg(test)]
+mod tests {
+    use icepick::score::{Folding, Scheme};
+    use icepick::scorer::{Scorer, Fuzzy, Extended};
+    use icepick::explain::Explanation;
+    use icepick::config::Config;
+    use icepick::search::Search;
+    use icepick::sorted_result_set::Tiebreak;
+
+    pub fn explain<S: Scorer>(mut scorer: S, choice: &str, query: &str, index: usize, tiebreaks: &[Tiebreak]) -> Explanation {
+        scorer.prepare(query, Folding::default());
+        let matching = scorer.score(choice).unwrap();
+        Explanation::new(&matching, scorer.explain(&matching), index, tiebreaks)
+    }
+
+    #[test]
+    fn breaks_a_fuzzy_quality_down_into_bonuses_and_gaps() {
+        let explanation = explain(Fuzzy::default(), "src/screen.rs", "scr", 3, &[Tiebreak::Length]);
+
+        let components: Vec<(&str, usize)> = explanation.components.iter()
+            .map(|component| (component.name, component.count))
+            .collect();
+        assert_eq!(components, vec![("after slash", 1), ("consecutive", 2), ("leading gap", 4), ("trailing gap", 6)]);
+        assert_eq!(explanation.positions, vec![4, 5, 6]);
+        assert_eq!(explanation.window, 3);
+        assert_eq!(explanation.tiebreaks, vec![(Tiebreak::Length, 13)]);
+
+        let total = explanation.components.iter().fold(0.0, |sum, component| sum + component.score);
+        assert!((total - explanation.quality).abs() < 0.0001);
+    }
+
+    #[test]
+    fn reports_what_the_bonuses_do_not_explain_as_other() {
+        let explanation = explain(Extended::with_scheme(Scheme::Path), "src/screen.rs", "scr", 0, &[]);
+
+        let other = explanation.components.iter().find(|component| component.name == "other").unwrap();
+        // The file name bonus, and the leading gap that scoring the file name alone never charged.
+        assert!((other.score - 2.02).abs() < 0.0001);
+    }
+
+    #[test]
+    fn explains_every_result_with_its_input_index() {
+        let choices = vec!["scripts/ci/run.rs".to_string(), "README.md".to_string(), "src/screen.rs".to_string()];
+        let config = Config { initial_query: Some("scr".to_string()), scheme: Scheme::Path, ..Config::default() };
+        let explanations = Search::configured(&choices, config, 10).explain();
+
+        let indices: Vec<usize> = explanations.iter().map(|explanation| explanation.index).collect();
+        assert_eq!(indices, vec![2, 0]);
+        assert!(format!("{}", explanations[0]).starts_with("#2 src/screen.rs\n  positions       4, 5, 6\n"));
+    }
+
+    #[test]
+    fn reports_an_exact_match_without_breaking_it_down() {
+        let explanation = explain(Fuzzy::default(), "abc", "abc", 0, &[]);
+
+        assert!(explanation.components.is_empty());
+        assert_eq!(format!("{}", explanation), "#0 abc\n  positions       0, 1, 2\n  window          3\n  quality         exact match\n");
+    }
+
+    #[test]
+    fn adds_up_the_breakdown_of_every_extended_query_term() {
+        let explanation = explain(Extended::default(), "src/screen.rs", "'scr rs !x", 0, &[]);
+
+        let components: Vec<(&str, usize)> = explanation.components.iter()
+            .map(|component| (component.name, component.count))
+            .collect();
+        assert_eq!(components, vec![("after slash", 1), ("consecutive", 3), ("leading gap", 15), ("trailing gap", 6), ("after dot", 1)]);
+        let total = explanation.components.iter().fold(0.0, |sum, component| sum + component.score);
+        assert!((total - explanation.quality).abs() < 0.0001);
+    }
+}
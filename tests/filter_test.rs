@@ -0,0 +1,87 @@
+extern crate icepick;
+
+#[cfg(test)]
+mod tests {
+    use icepick::search::Search;
+    use icepick::filter::Worker;
+    use icepick::score::Case;
+    use std::sync::atomic::AtomicBool;
+
+    fn choices() -> Vec<String> {
+        vec!["one".to_string(), "two".to_string(), "three".to_string()]
+    }
+
+    #[test]
+    fn keeps_the_previous_results_until_the_filter_finishes() {
+        let search = Search::blank(&choices(), None, 10).edit_query("tw");
+        assert!(search.filtering);
+        assert_eq!(search.num_matches(), 3);
+
+        let filtered = search.filter().run(&AtomicBool::new(false)).unwrap();
+        let search = search.with_filtered(filtered);
+        assert!(!search.filtering);
+        assert_eq!(search.num_matches(), 1);
+        assert_eq!(search.selection(), Some("two".to_string()));
+    }
+
+    #[test]
+    fn gives_up_once_cancelled() {
+        let search = Search::blank(&choices(), None, 10).edit_query("t");
+
+        assert!(search.filter().run(&AtomicBool::new(true)).is_none());
+    }
+
+    #[test]
+    fn keeps_filtering_while_the_query_differs_from_the_finished_one() {
+        let search = Search::blank(&choices(), None, 10).edit_query("t");
+        let filter = search.filter();
+        let search = search.edit_query("th");
+
+        let search = search.with_filtered(filter.run(&AtomicBool::new(false)).unwrap());
+        assert!(search.filtering);
+        assert_eq!(search.num_matches(), 2);
+    }
+
+    #[test]
+    fn drops_results_filtered_with_other_settings() {
+        let search = Search::blank(&choices(), None, 10).edit_query("T");
+        let filter = search.filter();
+        let search = search.with_case(Case::Respect);
+
+        let search = search.with_filtered(filter.run(&AtomicBool::new(false)).unwrap());
+        assert_eq!(search.num_matches(), 0);
+    }
+
+    #[test]
+    fn filters_choices_that_arrived_while_filtering() {
+        let search = Search::blank(&choices(), None, 10).edit_query("tw");
+        let filter = search.filter();
+        let search = search.append_choices(vec!["twice"]);
+
+        let search = search.with_filtered(filter.run(&AtomicBool::new(false)).unwrap());
+        assert_eq!(search.num_matches(), 2);
+        assert_eq!(search.backspace().append_to_search("w").num_matches(), 2);
+    }
+
+    #[test]
+    fn counts_the_matches_of_a_cached_query_dropped_while_filtering() {
+        let search = Search::blank(&choices(), None, 10).append_to_search("t").append_to_search("w").edit_query("t");
+        let filter = search.filter();
+        let search = search.append_choices(vec!["ten"]);
+
+        let search = search.with_filtered(filter.run(&AtomicBool::new(false)).unwrap());
+        assert_eq!(search.num_matches(), 3);
+    }
+
+    #[test]
+    fn filters_on_a_thread_of_its_own() {
+        let mut worker = Worker::new();
+        let search = Search::blank(&choices(), None, 10).edit_query("o");
+        worker.start(search.filter());
+
+        let filtered = loop {
+            if let Some(filtered) = worker.finished() { break filtered }
+        };
+        assert_eq!(search.with_filtered(filtered).num_matches(), 2);
+    }
+}
@@ -0,0 +1,73 @@
+extern crate icepick;
+
+#[cfg(test)]
+mod tests {
+    use icepick::index::{self, Signature, Candidates};
+    use icepick::choices::Choices;
+    use icepick::scorer::{Scorer, Extended};
+    use icepick::score::{Folding, Scheme};
+    use icepick::search::Search;
+
+    #[test]
+    fn signatures_contain_the_characters_of_their_text() {
+        let choice = Signature::of("src/Screen.rs");
+
+        assert!(choice.contains(Signature::of("scr")));
+        assert!(choice.contains(Signature::of("SCR")));
+        assert!(!choice.contains(Signature::of("scx")));
+    }
+
+    #[test]
+    fn signatures_are_taken_from_the_most_permissive_folding() {
+        assert!(Signature::of("Straße").contains(Signature::of("ss")));
+        assert!(Signature::of("Café").contains(Signature::of("cafe")));
+    }
+
+    #[test]
+    fn candidates_are_the_input_positions_of_choices() {
+        let mut choices = Choices::from(vec!["one".to_string(), "two".to_string()]);
+        assert_eq!(index::candidates(&choices), vec![0, 1]);
+        assert_eq!(choices.entry(1).map(|(_, signature)| signature), Some(Signature::of("two")));
+
+        choices.extend(vec!["three"]);
+        assert_eq!(index::candidates_from(2, &choices), vec![2]);
+    }
+
+    #[test]
+    fn extended_only_requires_characters_every_alternative_has() {
+        let mut scorer = Extended::default();
+        scorer.prepare("on | no !x", Folding::default());
+        assert_eq!(scorer.signature(), Signature::of("no"));
+
+        scorer.prepare("on | t", Folding::default());
+        assert_eq!(scorer.signature(), Signature::default());
+
+        let mut paths = Extended::with_scheme(Scheme::Path);
+        paths.prepare("s/scr", Folding::default());
+        assert_eq!(paths.signature(), Signature::of("scr"));
+    }
+
+    #[test]
+    fn skips_choices_lacking_a_query_character() {
+        let choices = vec!["one".to_string(), "two".to_string(), "three".to_string()];
+        let search = Search::blank(&choices, None, 20).append_to_search("t").append_to_search("e");
+
+        assert_eq!(search.num_matches(), 1);
+        assert_eq!(search.selection(), Some("three".to_string()));
+    }
+
+    #[test]
+    fn candidates_grow_without_changing_their_clones() {
+        let mut candidates = Candidates::from(vec![0, 1]);
+        let clone = candidates.clone();
+        candidates.extend(vec![2, 3]);
+
+        assert_eq!(clone.iter().cloned().collect::<Vec<u32>>(), vec![0, 1]);
+        assert_eq!(candidates.parts(), [&[0, 1][..], &[2, 3][..]]);
+
+        drop(clone);
+        candidates.extend(vec![4]);
+        assert_eq!(candidates.parts(), [&[0, 1, 2, 3, 4][..], &[][..]]);
+        assert_eq!(candidates.len(), 5);
+    }
+}
@@ -0,0 +1,46 @@
+extern crate icepick;
+
+#[cfg(test)]
+mod tests {
+    use icepick::marks::{Marks, MarkOrder};
+
+    #[test]
+    fn toggles_a_mark() {
+        let mut marks = Marks::new(None);
+
+        marks.toggle(3);
+        assert!(marks.contains(3));
+        marks.toggle(3);
+        assert!(!marks.contains(3));
+        assert!(marks.is_empty());
+    }
+
+    #[test]
+    fn marks_no_more_than_the_limit() {
+        let mut marks = Marks::new(Some(2));
+        for index in 0..5 {
+            marks.mark(index);
+        }
+
+        assert!(marks.is_full());
+        assert_eq!(marks.in_order(MarkOrder::Input), vec![0, 1]);
+    }
+
+    #[test]
+    fn keeps_the_marking_order() {
+        let mut marks = Marks::new(None);
+        for &index in [4, 1, 7, 1].iter() {
+            marks.mark(index);
+        }
+        marks.unmark(7);
+
+        assert_eq!(marks.in_order(MarkOrder::Marking), vec![4, 1]);
+        assert_eq!(marks.in_order(MarkOrder::Input), vec![1, 4]);
+    }
+
+    #[test]
+    fn parses_mark_orders() {
+        assert_eq!("marking".parse::<MarkOrder>(), Ok(MarkOrder::Marking));
+        assert!("reverse".parse::<MarkOrder>().is_err());
+    }
+}
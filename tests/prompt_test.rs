@@ -0,0 +1,129 @@
+extern crate icepick;
+
+#[cfg(test)]
+mod tests {
+    use icepick::prompt::Prompt;
+
+    fn prompt(text: &str) -> Prompt {
+        let mut prompt = Prompt::default();
+        prompt.set_text(text);
+        prompt
+    }
+
+    #[test]
+    fn inserts_at_the_cursor() {
+        let mut prompt = prompt("sc");
+        prompt.left();
+        prompt.insert("r");
+
+        assert_eq!(prompt.text(), "src");
+        assert_eq!(prompt.cursor(), 2);
+    }
+
+    #[test]
+    fn moves_by_characters_not_bytes() {
+        let mut prompt = prompt("añb");
+        prompt.left();
+        prompt.left();
+
+        assert_eq!(prompt.before_cursor(), "a");
+        prompt.delete_forwards();
+        assert_eq!(prompt.text(), "ab");
+    }
+
+    #[test]
+    fn moves_to_the_start_and_end() {
+        let mut prompt = prompt("src");
+        prompt.start();
+        prompt.left();
+        assert_eq!(prompt.cursor(), 0);
+
+        prompt.end();
+        prompt.right();
+        assert_eq!(prompt.cursor(), 3);
+    }
+
+    #[test]
+    fn moves_by_words() {
+        let mut prompt = prompt("src/screen.rs");
+        prompt.word_left();
+        assert_eq!(prompt.cursor(), 11);
+        prompt.word_left();
+        assert_eq!(prompt.cursor(), 4);
+
+        prompt.word_right();
+        assert_eq!(prompt.cursor(), 10);
+    }
+
+    #[test]
+    fn deletes_around_the_cursor() {
+        let mut prompt = prompt("abc");
+        prompt.left();
+        prompt.delete_backwards();
+        assert_eq!(prompt.text(), "ac");
+
+        prompt.delete_forwards();
+        assert_eq!(prompt.text(), "a");
+        prompt.delete_forwards();
+        assert_eq!(prompt.text(), "a");
+    }
+
+    #[test]
+    fn kills_back_to_whitespace() {
+        let mut prompt = prompt("'src lib/sco ");
+        prompt.kill_word_backwards();
+
+        assert_eq!(prompt.text(), "'src ");
+    }
+
+    #[test]
+    fn kills_to_either_end() {
+        let mut prompt = prompt("src test");
+        prompt.word_left();
+        prompt.kill_to_end();
+        assert_eq!(prompt.text(), "src ");
+
+        prompt.left();
+        prompt.kill_to_start();
+        assert_eq!(prompt.text(), " ");
+        assert_eq!(prompt.cursor(), 0);
+    }
+
+    #[test]
+    fn yanks_the_last_kill() {
+        let mut prompt = prompt("one two");
+        prompt.kill_word_backwards();
+        prompt.start();
+        prompt.yank();
+
+        assert_eq!(prompt.text(), "twoone ");
+        assert_eq!(prompt.cursor(), 3);
+    }
+
+    #[test]
+    fn yanks_earlier_kills_right_after_a_yank() {
+        let mut prompt = prompt("one two");
+        prompt.kill_word_backwards();
+        prompt.kill_word_backwards();
+        prompt.yank();
+        prompt.yank_previous();
+        assert_eq!(prompt.text(), "two");
+
+        prompt.yank_previous();
+        assert_eq!(prompt.text(), "one ");
+        prompt.left();
+        prompt.yank_previous();
+        assert_eq!(prompt.text(), "one ");
+    }
+
+    #[test]
+    fn keeps_the_cursor_while_the_text_stays_the_same() {
+        let mut prompt = prompt("src");
+        prompt.left();
+        prompt.set_text("src");
+        assert_eq!(prompt.cursor(), 2);
+
+        prompt.set_text("lib");
+        assert_eq!(prompt.cursor(), 3);
+    }
+}
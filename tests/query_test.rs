@@ -0,0 +1,61 @@
+extern crate icepick;
+
+#[cfg(test)]
+mod tests {
+    use icepick::query::{Query, Term, Kind};
+
+    pub fn term(kind: Kind, text: &str, negated: bool) -> Term {
+        Term { kind: kind, text: text.to_string(), negated: negated }
+    }
+
+    #[test]
+    fn splits_terms_on_spaces() {
+        let query = Query::parse("foo  bar");
+        assert_eq!(query.groups, vec![vec![term(Kind::Fuzzy, "foo", false)],
+                                      vec![term(Kind::Fuzzy, "bar", false)]]);
+    }
+
+    #[test]
+    fn parses_anchors_and_exact_terms() {
+        let query = Query::parse("^src rs$ 'scr ^main.rs$");
+        assert_eq!(query.groups, vec![vec![term(Kind::Prefix, "src", false)],
+                                      vec![term(Kind::Suffix, "rs", false)],
+                                      vec![term(Kind::Exact, "scr", false)],
+                                      vec![term(Kind::Line, "main.rs", false)]]);
+    }
+
+    #[test]
+    fn negated_terms_match_exactly() {
+        let query = Query::parse("!test !^target");
+        assert_eq!(query.groups, vec![vec![term(Kind::Exact, "test", true)],
+                                      vec![term(Kind::Prefix, "target", true)]]);
+    }
+
+    #[test]
+    fn groups_alternatives() {
+        let query = Query::parse("^src rs$ | toml$");
+        assert_eq!(query.groups, vec![vec![term(Kind::Prefix, "src", false)],
+                                      vec![term(Kind::Suffix, "rs", false), term(Kind::Suffix, "toml", false)]]);
+    }
+
+    #[test]
+    fn ignores_empty_terms() {
+        assert!(Query::parse(" ^ ! ' $ ").is_empty());
+        assert_eq!(Query::parse("| a |").groups, vec![vec![term(Kind::Fuzzy, "a", false)]]);
+    }
+
+    #[test]
+    fn only_plain_terms_narrow_when_appending() {
+        assert!(Query::parse("^src rs$").narrows_when_appending());
+        assert!(!Query::parse("src !test").narrows_when_appending());
+        assert!(!Query::parse("rs | toml").narrows_when_appending());
+    }
+
+    #[test]
+    fn loses_an_anchor_when_typing_on_after_it() {
+        assert!(Query::keeps_anchors("rs$", "rs$"));
+        assert!(Query::keeps_anchors("rs$", "rs$ src"));
+        assert!(!Query::keeps_anchors("rs$", "rs$c"));
+        assert!(Query::keeps_anchors("^src", "^src/"));
+    }
+}
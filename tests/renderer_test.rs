@@ -3,10 +3,11 @@ extern crate icepick;
 #[cfg(test)]
 mod tests {
     pub use icepick::search::Search;
-    pub use icepick::text::Text;
-    pub use icepick::renderer::Renderer;
+    pub use icepick::text::{Text, Window};
+    pub use icepick::renderer::{Renderer, MARKED, UNMARKED};
     pub use icepick::score::Match;
     pub use icepick::score::Quality;
+    pub use icepick::score::Case;
 
     #[test]
     fn renders_selected_matches_with_a_highlight() {
@@ -17,15 +18,57 @@ mod tests {
         let renderer = Renderer;
 
         let search = Search::blank(&choices, None, 2).down();
-        let output = renderer.render(&search);
+        let output = renderer.render(&search, 80);
         let ref text = choices[1];
 
         assert_eq!(vec![Text::Normal("3 > ".to_string()),
-        Text::Colored(Match::with_empty_range(text)),
-        Text::Highlight("one".to_string())], output);
+        Text::Colored(Match::with_empty_range(text), Window { start: 0, width: 80 }),
+        Text::Highlight(Match::with_empty_range(text), Window { start: 0, width: 80 })], output);
 
     }
 
+    #[test]
+    fn renders_the_results_from_the_top_of_the_screen() {
+        let choices = vec!["one".to_string(),
+        "two".to_string(),
+        "three".to_string()];
+
+        let renderer = Renderer;
+
+        let search = Search::blank(&choices, None, 2).last();
+        let output = renderer.render(&search, 80);
+
+        assert_eq!(vec![Text::Normal("3 > ".to_string()),
+        Text::Colored(Match::with_empty_range(&choices[1]), Window { start: 0, width: 80 }),
+        Text::Highlight(Match::with_empty_range(&choices[2]), Window { start: 0, width: 80 })], output);
+    }
+
+    #[test]
+    fn renders_a_gutter_with_the_marks() {
+        let choices = vec!["one".to_string(),
+        "two".to_string()];
+
+        let renderer = Renderer;
+
+        let search = Search::blank(&choices, None, 3).with_multi(None).toggle_mark();
+        let output = renderer.render(&search, 80);
+
+        assert_eq!(vec![Text::Normal("2 [1 marked] > ".to_string()),
+        Text::Gutter(MARKED, Box::new(Text::Colored(Match::with_empty_range(&choices[0]), Window { start: 0, width: 78 }))),
+        Text::Gutter(UNMARKED, Box::new(Text::Highlight(Match::with_empty_range(&choices[1]), Window { start: 0, width: 78 }))),
+        Text::Blank], output);
+    }
+
+    #[test]
+    fn shows_the_limit_of_marks() {
+        let choices = vec!["one".to_string()];
+        let renderer = Renderer;
+
+        let search = Search::blank(&choices, None, 1).with_multi(Some(5));
+
+        assert_eq!(renderer.header(&search), "1 [0/5 marked] > ");
+    }
+
     #[test]
     fn renders_a_mismatch() {
         let choices = vec!["one".to_string(),
@@ -35,10 +78,105 @@ mod tests {
         let renderer = Renderer;
 
         let search = Search::blank(&choices, None, 2).append_to_search("z");
-        let output = renderer.render(&search);
+        let output = renderer.render(&search, 80);
 
         assert_eq!(vec![Text::Normal("0 > z".to_string()),
         Text::Blank,
         Text::Blank], output);
     }
+
+    #[test]
+    fn renders_the_matched_positions_of_each_choice() {
+        let choices = vec!["one".to_string(),
+        "neon".to_string()];
+
+        let renderer = Renderer;
+
+        let search = Search::blank(&choices, None, 2).append_to_search("n");
+        let output = renderer.render(&search, 80);
+
+        match (&output[1], &output[2]) {
+            (&Text::Highlight(ref first, _), &Text::Colored(ref second, _)) => {
+                assert_eq!((first.original.as_ref(), first.positions.clone()), ("neon", vec![0]));
+                assert_eq!((second.original.as_ref(), second.positions.clone()), ("one", vec![1]));
+            },
+            _ => panic!("expected a highlighted and a colored match, got {:?}", output),
+        }
+    }
+
+    #[test]
+    fn shows_the_case_mode_unless_case_is_ignored() {
+        let choices = vec!["one".to_string()];
+        let renderer = Renderer;
+
+        let search = Search::blank(&choices, None, 1).with_case(Case::Smart);
+
+        assert_eq!(renderer.header(&search), "1 [smart] > ");
+    }
+
+    #[test]
+    fn shows_the_vi_mode() {
+        let choices = vec!["one".to_string()];
+        let renderer = Renderer;
+
+        let search = Search::blank(&choices, None, 1).with_vi();
+
+        assert_eq!(renderer.header(&search), "1 [insert] > ");
+    }
+
+    #[test]
+    fn shows_that_choices_are_still_loading() {
+        let choices = vec!["one".to_string()];
+        let renderer = Renderer;
+
+        let search = Search::blank(&choices, None, 1).with_loading(true).append_to_search("o");
+
+        assert_eq!(renderer.header(&search), "1 [loading] > o");
+    }
+
+    #[test]
+    fn shows_that_the_query_is_still_being_filtered() {
+        let choices = vec!["one".to_string(), "two".to_string()];
+        let renderer = Renderer;
+
+        let search = Search::blank(&choices, None, 2).append_to_search("o").edit_query("ox");
+
+        assert_eq!(renderer.header(&search), "2 [filtering…] > ox");
+    }
+
+    #[test]
+    fn does_not_scroll_choices_that_fit() {
+        let choice = "src/screen.rs".to_string();
+        let matching = Match::new(Quality(1.0), vec![12], &choice);
+
+        assert_eq!(Renderer.window(&matching, 13, 0), Window { start: 0, width: 13 });
+        assert_eq!(Renderer.window(&matching, 13, 8), Window { start: 0, width: 13 });
+    }
+
+    #[test]
+    fn scrolls_long_choices_to_show_the_match() {
+        let choice = "a/very/long/path/to/the/match.rs".to_string();
+        let matching = Match::new(Quality(1.0), vec![24, 25, 26], &choice);
+
+        let window = Renderer.window(&matching, 16, 0);
+        assert_eq!(window, Window { start: 15, width: 16 });
+    }
+
+    #[test]
+    fn keeps_the_first_matched_character_visible() {
+        let choice = "match/in/a/very/long/path/at.rs".to_string();
+        let matching = Match::new(Quality(1.0), vec![0, 29], &choice);
+
+        assert_eq!(Renderer.window(&matching, 16, 0).start, 0);
+    }
+
+    #[test]
+    fn scrolls_by_the_given_amount_within_bounds() {
+        let choice = "a/very/long/path/to/the/match.rs".to_string();
+        let matching = Match::new(Quality(1.0), vec![0], &choice);
+
+        assert_eq!(Renderer.window(&matching, 16, 8).start, 8);
+        assert_eq!(Renderer.window(&matching, 16, 100).start, 18);
+        assert_eq!(Renderer.window(&matching, 16, -8).start, 0);
+    }
 }
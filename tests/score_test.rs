@@ -7,13 +7,13 @@ mod tests {
 
     pub fn do_score<'a>(choice: &'a String, query: &str) -> Option<Match<'a>> {
        let query_stirng = query.to_string();
-       score::score(&choice,  &query_stirng)
+       score::score(&choice,  &query_stirng, Folding::default())
     }
 
     pub fn match_quality(choice: &str, query: &str) -> f32 {
         let choice_stirng = choice.to_string();
         let query_stirng = query.to_string();
-        let matching = score::score(&choice_stirng, &query_stirng);
+        let matching = score::score(&choice_stirng, &query_stirng, Folding::default());
         let Quality(quality) = matching.unwrap().quality;
         quality
     }
@@ -21,11 +21,28 @@ mod tests {
     pub fn match_substring(choice: &str, query: &str) -> String {
         let choice_stirng = choice.to_string();
         let query_stirng = query.to_string();
-        let (_,substring,_) = score::score(&choice_stirng, &query_stirng).unwrap().parts();
+        let (_,substring,_) = score::score(&choice_stirng, &query_stirng, Folding::default()).unwrap().parts();
+        substring
+    }
+
+    pub fn fuzzy_quality(choice: &str, query: &str) -> f32 {
+        let choice_string = choice.to_string();
+        let query_string = query.to_string();
+        score::fuzzy(&choice_string, &query_string, Folding::default()).unwrap().quality.to_f32()
+    }
+
+    pub fn fuzzy_substring(choice: &str, query: &str) -> String {
+        let choice_string = choice.to_string();
+        let query_string = query.to_string();
+        let (_,substring,_) = score::fuzzy(&choice_string, &query_string, Folding::default()).unwrap().parts();
         substring
     }
 
     pub use icepick::score::Quality;
+    pub use icepick::score::Case;
+    pub use icepick::score::Folding;
+
+    const SENSITIVE: Folding = Folding { case_sensitive: true, strip_diacritics: false };
 
     #[test]
     fn scores_greater_than_zero_and_shows_match() {
@@ -116,4 +133,215 @@ mod tests {
     fn tighter_matches_score_higher() {
         assert!(match_quality("long 12 long", "12") > match_quality("1 long 2", "12"));
     }
+
+    #[test]
+    fn fuzzy_does_not_match_when_a_character_is_missing() {
+        let input = "abc".to_string();
+        assert_eq!(score::fuzzy(&input, &"abd".to_string(), Folding::default()), None);
+        assert_eq!(score::fuzzy(&input, &"cb".to_string(), Folding::default()), None);
+    }
+
+    #[test]
+    fn fuzzy_scores_exact_matches_highest() {
+        assert!(fuzzy_quality("abc", "abc") > fuzzy_quality("abcd", "abc"));
+        assert!(fuzzy_quality("src", "src") > fuzzy_quality("src/screen.rs", "src"));
+    }
+
+    #[test]
+    fn fuzzy_prefers_consecutive_characters() {
+        assert!(fuzzy_quality("app/models/foo", "amo") > fuzzy_quality("app/mxxxoxxx", "amo"));
+    }
+
+    #[test]
+    fn fuzzy_prefers_matches_at_word_starts() {
+        assert!(fuzzy_quality("src/screen.rs", "sc") > fuzzy_quality("misc.rs", "sc"));
+        assert!(fuzzy_quality("amazing-bar", "ab") > fuzzy_quality("fooabar", "ab"));
+        assert!(fuzzy_quality("amazing_bar", "ab") > fuzzy_quality("fooabar", "ab"));
+        assert!(fuzzy_quality("amazing.bar", "ab") > fuzzy_quality("fooabar", "ab"));
+    }
+
+    #[test]
+    fn fuzzy_prefers_camel_case_humps() {
+        assert!(fuzzy_quality("FooBar", "fb") > fuzzy_quality("foobar", "fb"));
+    }
+
+    #[test]
+    fn fuzzy_penalizes_gaps() {
+        assert!(fuzzy_quality("ab", "a") > fuzzy_quality("abc", "a"));
+        assert!(fuzzy_quality("axxb", "ab") > fuzzy_quality("axxxxb", "ab"));
+    }
+
+    #[test]
+    fn fuzzy_picks_the_best_alignment_not_the_first() {
+        assert_eq!(&fuzzy_substring("a/bx/bar", "bar")[..], "bar");
+        assert_eq!(&fuzzy_substring("misc/screen.rs", "sc")[..], "sc");
+    }
+
+    #[test]
+    fn exposes_every_matched_position() {
+        let choice = "app/models/foo".to_string();
+        let repeated = "a/b/ab".to_string();
+        assert_eq!(score::fuzzy(&choice, &"amo".to_string(), Folding::default()).unwrap().positions, vec![0, 4, 5]);
+        assert_eq!(score::score(&repeated, &"ab".to_string(), Folding::default()).unwrap().positions, vec![4, 5]);
+    }
+
+    #[test]
+    fn smart_case_is_sensitive_once_the_query_has_upper_case_letters() {
+        assert!(!Case::Smart.is_sensitive("readme"));
+        assert!(Case::Smart.is_sensitive("README"));
+        assert!(!Case::Ignore.is_sensitive("README"));
+        assert!(Case::Respect.is_sensitive("readme"));
+    }
+
+    #[test]
+    fn matches_case_sensitively_when_asked_to() {
+        let choice = "README.md".to_string();
+        assert!(score::fuzzy(&choice, &"RM".to_string(), SENSITIVE).is_some());
+        assert_eq!(score::fuzzy(&choice, &"rm".to_string(), SENSITIVE), None);
+        assert_eq!(score::score(&choice, &"rm".to_string(), SENSITIVE), None);
+    }
+
+    #[test]
+    fn parses_case_modes() {
+        assert_eq!("smart".parse(), Ok(Case::Smart));
+        assert_eq!("respect".parse(), Ok(Case::Respect));
+        assert!("loud".parse::<Case>().is_err());
+    }
+
+    #[test]
+    fn matches_non_ascii_characters_by_character_position() {
+        let choice = "héllo wörld".to_string();
+        let matching = score::score(&choice, &"wö".to_string(), Folding::default()).unwrap();
+
+        assert_eq!(matching.positions, vec![6, 7]);
+        assert_eq!(&matching.parts().1[..], "wö");
+    }
+
+    #[test]
+    fn ignores_case_of_non_ascii_letters() {
+        let choice = "ÉCOLE".to_string();
+        let query = Folding::default().fold_query("École");
+
+        assert!(score::fuzzy(&choice, &query, Folding::default()).is_some());
+        assert_eq!(score::fuzzy(&choice, &"école".to_string(), SENSITIVE), None);
+    }
+
+    #[test]
+    fn folds_sharp_s_into_two_characters() {
+        let choice = "Straße".to_string();
+        let matching = score::substring(&choice, &"asse".to_string(), Folding::default()).unwrap();
+
+        assert_eq!(matching.positions, vec![3, 4, 5]);
+    }
+
+    #[test]
+    fn optionally_ignores_diacritics() {
+        let without_diacritics = Folding { case_sensitive: false, strip_diacritics: true };
+        let choice = "Café Zoë".to_string();
+        let decomposed = "Cafe\u{301}".to_string();
+
+        assert_eq!(score::fuzzy(&choice, &"cafe".to_string(), Folding::default()), None);
+        assert_eq!(score::fuzzy(&choice, &"cafe zoe".to_string(), without_diacritics).unwrap().positions,
+                   vec![0, 1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(score::prefix(&decomposed, &"cafe".to_string(), without_diacritics).unwrap().positions,
+                   vec![0, 1, 2, 3]);
+        assert_eq!(without_diacritics.fold_query("ÉTÉ"), "ete");
+    }
+
+    #[test]
+    fn scores_ascii_choices_like_the_ones_they_fold_into() {
+        let without_diacritics = Folding { case_sensitive: false, strip_diacritics: true };
+        let ascii = "src/Search_Tests.rs".to_string();
+        let accented = "src/Séarch_Tésts.rs".to_string();
+
+        for query in ["srchts", "search", "seacrh_tests"].iter() {
+            let query = query.to_string();
+            assert_eq!(score::fuzzy(&ascii, &query, without_diacritics).map(|m| (m.quality, m.positions)),
+                       score::fuzzy(&accented, &query, without_diacritics).map(|m| (m.quality, m.positions)));
+            assert_eq!(score::tolerant(&ascii, &query, without_diacritics).map(|m| (m.quality, m.positions)),
+                       score::tolerant(&accented, &query, without_diacritics).map(|m| (m.quality, m.positions)));
+        }
+        assert!(score::fuzzy_matches(&ascii, &"srs_t".to_string(), Folding::default()));
+        assert!(!score::fuzzy_matches(&ascii, &"srs_t".to_string(), SENSITIVE));
+    }
+
+    #[test]
+    fn tolerant_forgives_a_wrong_swapped_or_extra_character() {
+        let choice = "search".to_string();
+
+        assert_eq!(score::tolerant(&choice, &"seerch".to_string(), Folding::default()).unwrap().positions,
+                   vec![0, 1, 3, 4, 5]);
+        assert_eq!(score::tolerant(&choice, &"saerch".to_string(), Folding::default()).unwrap().positions,
+                   vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(score::tolerant(&choice, &"searxch".to_string(), Folding::default()).unwrap().positions,
+                   vec![0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn tolerant_forgives_more_typos_in_longer_queries() {
+        let choice = "search.rs".to_string();
+
+        assert_eq!(score::tolerant(&choice, &"sxa".to_string(), Folding::default()), None);
+        assert_eq!(score::tolerant(&choice, &"sxxrch".to_string(), Folding::default()), None);
+        assert!(score::tolerant(&choice, &"sxxrch.rs".to_string(), Folding::default()).is_some());
+    }
+
+    #[test]
+    fn tolerant_matches_rank_below_every_exact_match() {
+        let long = format!("s{}earch", "-".repeat(500));
+        let short = "search".to_string();
+        let file = "search.rs".to_string();
+        let exact = score::fuzzy(&long, &"search".to_string(), Folding::default()).unwrap();
+        let one_typo = score::tolerant(&short, &"seerch".to_string(), Folding::default()).unwrap();
+        let two_typos = score::tolerant(&file, &"seerch.rb".to_string(), Folding::default()).unwrap();
+
+        assert!(exact.quality.to_f32() > one_typo.quality.to_f32());
+        assert!(one_typo.quality.to_f32() > two_typos.quality.to_f32());
+    }
+
+    #[test]
+    fn path_prefers_matches_in_the_file_name() {
+        let screen = "src/screen.rs".to_string();
+        let scripts = "scripts/ci/run.rs".to_string();
+        let query = "scr".to_string();
+        let in_basename = score::path(&screen, &query, Folding::default()).unwrap();
+        let in_directory = score::path(&scripts, &query, Folding::default()).unwrap();
+
+        assert_eq!(in_basename.positions, vec![4, 5, 6]);
+        assert_eq!(in_directory.positions, vec![0, 1, 2]);
+        assert!(in_basename.quality.to_f32() > in_directory.quality.to_f32());
+    }
+
+    #[test]
+    fn path_scores_a_whole_file_name_finitely_and_above_longer_ones() {
+        let file = "src/main.rs".to_string();
+        let backup = "src/main.rs.orig".to_string();
+        let query = "main.rs".to_string();
+        let whole = score::path(&file, &query, Folding::default()).unwrap().quality.to_f32();
+
+        assert!(whole.is_finite());
+        assert!(whole > score::path(&backup, &query, Folding::default()).unwrap().quality.to_f32());
+    }
+
+    #[test]
+    fn path_matches_segment_prefixes_in_order() {
+        let choice = "src/bin/screen.rs".to_string();
+
+        assert_eq!(score::path(&choice, &"s/scr".to_string(), Folding::default()).unwrap().positions,
+                   vec![0, 8, 9, 10]);
+        assert_eq!(score::path(&choice, &"b/".to_string(), Folding::default()).unwrap().positions,
+                   vec![4]);
+        assert_eq!(score::path(&choice, &"scr/s".to_string(), Folding::default()), None);
+        assert_eq!(score::path(&choice, &"r/scr".to_string(), Folding::default()), None);
+    }
+
+    #[test]
+    fn path_prefers_segments_ending_in_the_file_name() {
+        let file = "src/main.rs".to_string();
+        let directory = "src/main/lib.rs".to_string();
+        let query = "s/main".to_string();
+
+        assert!(score::path(&file, &query, Folding::default()).unwrap().quality.to_f32() >
+                score::path(&directory, &query, Folding::default()).unwrap().quality.to_f32());
+    }
 }
@@ -0,0 +1,153 @@
+extern crate icepick;
+
+#[cfg(test)]
+mod tests {
+    use icepick::scorer::{Scorer, Fuzzy, Selecta, Prefix, Substring, Regex, Extended};
+    use icepick::search::Search;
+    use icepick::score::{Folding, Scheme};
+
+    pub fn positions<S: Scorer>(mut scorer: S, choice: &str, query: &str) -> Option<Vec<usize>> {
+        let choice_string = choice.to_string();
+        scorer.prepare(query, Folding::default());
+        scorer.score(&choice_string).map(|matching| matching.positions)
+    }
+
+    #[test]
+    fn fuzzy_matches_subsequences() {
+        assert_eq!(positions(Fuzzy::default(), "src/screen.rs", "scr"), Some(vec![4, 5, 6]));
+        assert_eq!(positions(Fuzzy::default(), "src/screen.rs", "xs"), None);
+    }
+
+    #[test]
+    fn selecta_matches_the_shortest_subsequence() {
+        assert_eq!(positions(Selecta::default(), "a/b/ab", "ab"), Some(vec![4, 5]));
+    }
+
+    #[test]
+    fn prefix_only_matches_at_the_start() {
+        assert_eq!(positions(Prefix::default(), "Search.rs", "sea"), Some(vec![0, 1, 2]));
+        assert_eq!(positions(Prefix::default(), "research.rs", "sea"), None);
+    }
+
+    #[test]
+    fn substring_matches_consecutive_characters() {
+        assert_eq!(positions(Substring::default(), "research.rs", "sea"), Some(vec![2, 3, 4]));
+        assert_eq!(positions(Substring::default(), "s_e_a", "sea"), None);
+    }
+
+    #[test]
+    fn regex_matches_patterns() {
+        assert_eq!(positions(Regex::default(), "src/screen.rs", "s.r"), Some(vec![4, 5, 6]));
+        assert_eq!(positions(Regex::default(), "src/screen.rs", "\\.rs$"), Some(vec![10, 11, 12]));
+        assert_eq!(positions(Regex::default(), "src/screen.rs", "^screen"), None);
+    }
+
+    #[test]
+    fn regex_matches_nothing_while_the_pattern_is_invalid() {
+        assert_eq!(positions(Regex::default(), "src/screen.rs", "(scr"), None);
+    }
+
+    #[test]
+    fn searches_with_the_given_scorer() {
+        let choices = vec!["research".to_string(), "search".to_string()];
+        let search = Search::blank_with_scorer(&choices, None, 10, Box::new(Prefix::default()));
+
+        assert_eq!(search.append_to_search("s").num_matches(), 1);
+    }
+
+    #[test]
+    fn widening_a_regex_brings_back_earlier_choices() {
+        let choices = vec!["one".to_string(), "two".to_string()];
+        let search = Search::blank_with_scorer(&choices, None, 10, Box::new(Regex::default()));
+
+        assert_eq!(search.append_to_search("o").append_to_search("n").append_to_search("?").num_matches(), 2);
+    }
+
+    #[test]
+    fn extended_requires_all_terms_in_any_order() {
+        assert_eq!(positions(Extended::default(), "src/screen.rs", "rs scr"), Some(vec![4, 5, 6, 11, 12]));
+        assert_eq!(positions(Extended::default(), "src/screen.rs", "rs xyz"), None);
+    }
+
+    #[test]
+    fn extended_excludes_negated_terms() {
+        assert_eq!(positions(Extended::default(), "tests/screen_test.rs", "scr !test"), None);
+        assert_eq!(positions(Extended::default(), "src/screen.rs", "scr !test"), Some(vec![4, 5, 6]));
+    }
+
+    #[test]
+    fn extended_anchors_terms() {
+        assert_eq!(positions(Extended::default(), "src/screen.rs", "^src"), Some(vec![0, 1, 2]));
+        assert_eq!(positions(Extended::default(), "src/screen.rs", "^scr"), None);
+        assert_eq!(positions(Extended::default(), "src/screen.rs", "rs$"), Some(vec![11, 12]));
+        assert_eq!(positions(Extended::default(), "src/screen.rs", "'creen"), Some(vec![5, 6, 7, 8, 9]));
+        assert_eq!(positions(Extended::default(), "src", "^src$"), Some(vec![0, 1, 2]));
+        assert_eq!(positions(Extended::default(), "src/screen.rs", "^src$"), None);
+    }
+
+    #[test]
+    fn extended_matches_any_alternative() {
+        assert_eq!(positions(Extended::default(), "Cargo.toml", "rs$ | toml$"), Some(vec![6, 7, 8, 9]));
+        assert_eq!(positions(Extended::default(), "README.md", "rs$ | toml$"), None);
+    }
+
+    #[test]
+    fn extended_adds_up_the_quality_of_each_term() {
+        let choice = "src/screen.rs".to_string();
+        let mut both = Extended::default();
+        let mut one = Extended::default();
+        both.prepare("'scr 'rs", Folding::default());
+        one.prepare("'scr", Folding::default());
+
+        assert!(both.score(&choice).unwrap().quality.to_f32() > one.score(&choice).unwrap().quality.to_f32());
+    }
+
+    #[test]
+    fn extended_ranks_exact_terms_on_the_scale_of_fuzzy_ones() {
+        let choices = vec!["xfoo/bar.r".to_string(), "foobar".to_string(), "foo/bar.rs".to_string()];
+        let search = Search::blank(&choices, None, 10).with_query("'foo bar");
+
+        let ranked: Vec<&str> = search.matches().iter().map(|matching| matching.original).collect();
+        assert_eq!(ranked, vec!["foo/bar.rs", "foobar", "xfoo/bar.r"]);
+    }
+
+    #[test]
+    fn extended_scores_fuzzy_terms_as_paths_with_the_path_scheme() {
+        let choices = vec!["scripts/ci/run.rs".to_string(), "src/screen.rs".to_string()];
+        let search = Search::blank_with_scorer(&choices, None, 10, Box::new(Extended::with_scheme(Scheme::Path)));
+        assert_eq!(search.append_to_search("s").append_to_search("c").append_to_search("r").selection(),
+                   Some("src/screen.rs".to_string()));
+
+        assert_eq!(positions(Extended::with_scheme(Scheme::Path), "src/screen.rs", "s/scr 'rs"),
+                   Some(vec![0, 4, 5, 6, 11, 12]));
+    }
+
+    #[test]
+    fn scores_never_exceed_the_upper_bound() {
+        let choices = vec!["src/screen.rs", "scr", "a/sc-r", "Ścreen_Scroll.rs", "xxxxxxxxxxsxcxrxxxxxxxxxx", "İstanbul/scr.rs"];
+        for query in vec!["scr", "sc | rs", "'scr !x", "^sc", "İscr", "'İs"] {
+            let mut scorer = Extended::default();
+            scorer.prepare(query, Folding::default());
+            for choice in choices.iter() {
+                let choice = choice.to_string();
+                let bound = scorer.upper_bound(&choice);
+                match scorer.score(&choice) {
+                    Some(matching) => {
+                        assert!(scorer.matches(&choice));
+                        assert!(matching.quality.to_f32() <= bound, "{} scores above {} for {}", choice, bound, query);
+                    },
+                    None => assert!(!scorer.matches(&choice)),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn long_choices_have_a_lower_upper_bound() {
+        let mut scorer = Fuzzy::default();
+        scorer.prepare("ab", Folding::default());
+
+        assert!(scorer.upper_bound(&"ab".to_string()).is_infinite());
+        assert!(scorer.upper_bound(&"a-b".to_string()) > scorer.upper_bound(&"a-long-way-to-b".to_string()));
+    }
+}
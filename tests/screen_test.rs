@@ -5,6 +5,9 @@ mod tests {
     pub use icepick::search::Search;
     pub use icepick::screen::Screen;
     pub use icepick::tty::TTY;
+    pub use icepick::config::Config;
+    pub use icepick::score::Case;
+    use std::sync::mpsc::channel;
 
     #[test]
     fn moves_the_selection_down_for_ctrl_n() {
@@ -33,7 +36,9 @@ mod tests {
 
         let search = Search::blank(&choices, None, 10).append_to_search("w").append_to_search("x");
         let result = screen.handle_keystroke(search, "\u{7f}");
-        assert_eq!(result.selection(), Some("two".to_string()));
+        assert_eq!(result.query, "w");
+        assert!(result.filtering);
+        assert_eq!(result.finish_filtering().selection(), Some("two".to_string()));
     }
 
     #[test]
@@ -62,15 +67,174 @@ mod tests {
         let choices = vec!["one".to_string(), "two".to_string()];
         let mut screen = Screen::fake_with_input(vec!["t"]);
 
-        let result = screen.run_search(choices, None);
-        assert_eq!(result.unwrap(), "two")
+        let result = screen.run_search(choices, Config::default());
+        assert_eq!(result, vec!["two"])
     }
 
     #[test]
     fn run_search_immediatly_done() {
         let choices = vec!["one".to_string(), "two".to_string()];
         let mut screen = Screen::fake_with_input(vec!["\n"]);
-        let result = screen.run_search(choices, None);
-        assert_eq!(result.unwrap(), "one")
+        let result = screen.run_search(choices, Config::default());
+        assert_eq!(result, vec!["one"])
+    }
+
+    #[test]
+    fn cycles_the_case_mode_for_ctrl_t() {
+        let choices = vec!["one".to_string(), "two".to_string()];
+        let screen = Screen::fake();
+
+        let search = Search::blank(&choices, None, 10);
+        let result = screen.handle_keystroke(search, "\u{14}");
+        assert_eq!(result.case, Case::Smart);
+    }
+
+    #[test]
+    fn places_the_cursor_after_the_query_by_display_width() {
+        let choices = vec!["日本".to_string()];
+        let mut screen = Screen::fake();
+
+        let search = Search::blank(&choices, None, 10).append_to_search("日");
+        screen.print(&search);
+
+        let lines = screen.ansi.io.lines();
+        assert_eq!(lines[lines.len() - 2], "\x1b[11;7H");
+    }
+
+    #[test]
+    fn places_the_cursor_at_the_edit_position() {
+        let choices = vec!["日本".to_string()];
+        let mut screen = Screen::fake();
+
+        let search = Search::blank(&choices, None, 10).append_to_search("日本");
+        let search = screen.handle_keystroke(search, "\u{1b}[D");
+        screen.print(&search);
+
+        let lines = screen.ansi.io.lines();
+        assert_eq!(lines[lines.len() - 2], "\x1b[11;7H");
+    }
+
+    #[test]
+    fn edits_the_query_at_the_cursor() {
+        let choices = vec!["one".to_string(), "two".to_string()];
+        let screen = Screen::fake();
+
+        let search = Search::blank(&choices, None, 10).append_to_search("tw");
+        let search = vec!["\u{1}", "\u{1b}[3~", "o", "\u{5}", "\u{7f}"].into_iter()
+            .fold(search, |search, key| screen.handle_keystroke(search, key));
+        assert_eq!(search.query, "o");
+        assert_eq!(search.finish_filtering().selection(), Some("one".to_string()));
+    }
+
+    #[test]
+    fn kills_and_yanks_the_query() {
+        let choices = vec!["one".to_string(), "two".to_string()];
+        let screen = Screen::fake();
+
+        let search = Search::blank(&choices, None, 10).append_to_search("o tw");
+        let search = screen.handle_keystroke(search, "\u{17}");
+        assert_eq!(search.query, "o ");
+        let search = screen.handle_keystroke(screen.handle_keystroke(search, "\u{15}"), "\u{19}");
+        assert_eq!(search.query, "o ");
+        assert_eq!(screen.handle_keystroke(search, "\u{1b}y").query, "tw");
+    }
+
+    #[test]
+    fn ignores_keys_without_a_binding() {
+        let choices = vec!["one".to_string()];
+        let screen = Screen::fake();
+
+        let search = screen.handle_keystroke(Search::blank(&choices, None, 10), "\u{1b}[2~");
+        assert_eq!(search.query, "");
+    }
+
+    #[test]
+    fn types_commands_in_vi_normal_mode() {
+        let choices = vec!["one".to_string(), "two".to_string()];
+        let screen = Screen::fake();
+
+        let search = Search::blank(&choices, None, 10).with_vi();
+        let search = vec!["o", "x", "\u{1b}", "x", "j"].into_iter()
+            .fold(search, |search, key| screen.handle_keystroke(search, key));
+        assert_eq!(search.query, "o");
+        assert_eq!(search.current, 1);
+
+        let search = screen.handle_keystroke(search, "\u{e}");
+        assert_eq!(search.current, 0);
+    }
+
+    #[test]
+    fn scrolls_the_highlighted_choice_for_ctrl_b_and_ctrl_f() {
+        let choices = vec!["a/very/long/path".to_string()];
+        let screen = Screen::fake();
+
+        let search = screen.handle_keystroke(Search::blank(&choices, None, 10), "\u{6}");
+        assert_eq!(search.scroll, 8);
+        let search = screen.handle_keystroke(search, "\u{2}");
+        assert_eq!(search.scroll, 0);
+    }
+
+    #[test]
+    fn pages_through_the_matches_for_page_down_and_page_up() {
+        let choices = (0..30).map(|n| n.to_string()).collect::<Vec<String>>();
+        let screen = Screen::fake();
+
+        let search = screen.handle_keystroke(Search::blank(&choices, None, 10), "\u{1b}[6~");
+        assert_eq!(search.selection(), Some("10".to_string()));
+        let search = screen.handle_keystroke(search, "\u{1b}[5~");
+        assert_eq!(search.selection(), Some("0".to_string()));
+    }
+
+    #[test]
+    fn jumps_to_the_last_and_first_match_for_end_and_home() {
+        let choices = (0..30).map(|n| n.to_string()).collect::<Vec<String>>();
+        let screen = Screen::fake();
+
+        let search = screen.handle_keystroke(Search::blank(&choices, None, 10), "\u{1b}[F");
+        assert_eq!(search.selection(), Some("29".to_string()));
+        let search = screen.handle_keystroke(search, "\u{1b}[H");
+        assert_eq!(search.selection(), Some("0".to_string()));
+    }
+
+    #[test]
+    fn marks_choices_with_tab_and_prints_them_all() {
+        let choices = vec!["one".to_string(), "two".to_string(), "three".to_string()];
+        let mut screen = Screen::fake_with_input(vec!["\n", "\t", "\u{e}", "\t"]);
+        let config = Config { multi: true, ..Config::default() };
+
+        let result = screen.run_search(choices, config);
+        assert_eq!(result, vec!["one", "three"]);
+    }
+
+    #[test]
+    fn types_tabs_without_multi() {
+        let choices = vec!["a\tb".to_string()];
+        let screen = Screen::fake();
+
+        let search = screen.handle_keystroke(Search::blank(&choices, None, 10), "\t");
+        assert_eq!(search.query, "\t");
+    }
+
+    #[test]
+    fn ignores_shift_tab_without_multi() {
+        let choices = vec!["one".to_string(), "two".to_string()];
+        let screen = Screen::fake();
+
+        let search = screen.handle_keystroke(Search::blank(&choices, None, 10).down(), "\u{1b}[Z");
+        assert_eq!(search.selection(), Some("two".to_string()));
+        assert_eq!(search.query, "");
+    }
+
+    #[test]
+    fn searches_choices_before_the_input_ends() {
+        let (sender, receiver) = channel();
+        let mut screen = Screen::fake_with_input(vec!["\n", "w", "t"]);
+        for line in vec!["one", "two", "three"] {
+            sender.send(line.to_string()).unwrap();
+        }
+
+        let result = screen.run_search_streaming(receiver, Config::default());
+        assert_eq!(result, vec!["two"]);
+        drop(sender);
     }
 }
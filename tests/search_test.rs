@@ -3,6 +3,11 @@ extern crate icepick;
 #[cfg(test)]
 mod tests {
     pub use icepick::search::Search;
+    pub use icepick::score::Case;
+    pub use icepick::search::Tolerance;
+    pub use icepick::sorted_result_set::Tiebreak;
+    pub use icepick::marks::MarkOrder;
+    use std::thread;
 
     pub fn input_times(n: usize) ->Vec<String> {
         let choices = vec!["choice".to_string()];
@@ -177,6 +182,31 @@ mod tests {
         search.backspace();
     }
 
+    #[test]
+    fn backspaces_over_an_initial_search() {
+        let choices = vec!["one".to_string(), "two".to_string(), "three".to_string()];
+        let search = Search::blank(&choices, Some("tw".to_string()), 20);
+        assert_eq!(search.num_matches(), 1);
+
+        let search = search.backspace();
+        assert_eq!(search.query, "t");
+        assert_eq!(search.num_matches(), 2);
+    }
+
+    #[test]
+    fn edits_anywhere_in_the_query_match_like_a_fresh_search() {
+        let choices = vec!["one".to_string(), "two".to_string(), "three".to_string(), "tree".to_string()];
+        let edited = Search::blank(&choices, None, 20)
+            .append_to_search("tre").append_to_search("e")
+            .with_query("the")
+            .with_query("tee");
+        let fresh = Search::blank(&choices, Some("tee".to_string()), 20);
+
+        assert_eq!(edited.num_matches(), fresh.num_matches());
+        assert_eq!(edited.result, fresh.result);
+        assert_eq!(edited.with_query("").num_matches(), 4);
+    }
+
     #[test]
     fn done_search_is_done() {
         let choices = vec!["one".to_string(),
@@ -232,4 +262,294 @@ mod tests {
 
         assert_eq!(search.result.len(), 20);
     }
+
+    #[test]
+    fn brings_back_choices_when_a_negation_gets_longer() {
+        let choices = vec!["one".to_string(),
+        "two".to_string(),
+        "three".to_string()];
+        let search = Search::blank(&choices, None, 20);
+
+        let search = search.append_to_search("!").append_to_search("t");
+        assert_eq!(search.num_matches(), 1);
+        assert_eq!(search.append_to_search("w").num_matches(), 2);
+    }
+
+    #[test]
+    fn smart_case_respects_case_for_upper_case_queries() {
+        let choices = vec!["One".to_string(),
+        "one".to_string()];
+        let search = Search::blank(&choices, None, 20).with_case(Case::Smart);
+
+        assert_eq!(search.append_to_search("o").num_matches(), 2);
+        let search = Search::blank(&choices, None, 20).with_case(Case::Smart);
+        assert_eq!(search.append_to_search("O").selection(), Some("One".to_string()));
+    }
+
+    #[test]
+    fn changing_the_case_mode_filters_again() {
+        let choices = vec!["One".to_string(),
+        "one".to_string()];
+        let search = Search::blank(&choices, None, 20).append_to_search("O");
+        assert_eq!(search.num_matches(), 2);
+
+        let search = search.cycle_case();
+        assert_eq!(search.case, Case::Smart);
+        assert_eq!(search.num_matches(), 1);
+        assert_eq!(search.backspace().num_matches(), 2);
+    }
+
+    #[test]
+    fn can_ignore_diacritics() {
+        let choices = vec!["résumé".to_string(),
+        "resume".to_string()];
+        let search = Search::blank(&choices, None, 20).append_to_search("e");
+        assert_eq!(search.num_matches(), 1);
+
+        assert_eq!(search.with_diacritics_ignored(true).num_matches(), 2);
+    }
+
+    #[test]
+    fn falls_back_to_typos_when_nothing_matches() {
+        let choices = vec!["search".to_string(),
+        "readme".to_string()];
+        let search = "seerch".chars().fold(Search::blank(&choices, None, 20),
+                                           |search, character| search.append_to_search(&character.to_string()));
+        assert_eq!(search.selection(), Some("search".to_string()));
+        assert_eq!(search.num_matches(), 1);
+
+        let search = search.backspace();
+        assert_eq!(search.num_matches(), 1);
+
+        let search = search.with_tolerance(Tolerance::Never);
+        assert_eq!(search.num_matches(), 0);
+    }
+
+    #[test]
+    fn always_tolerant_ranks_typos_below_exact_matches() {
+        let choices = vec!["search".to_string(),
+        "seerch".to_string()];
+        let search = Search::blank(&choices, None, 20).append_to_search("seerch");
+        assert_eq!(search.num_matches(), 1);
+
+        let search = search.with_tolerance(Tolerance::Always);
+        assert_eq!(search.num_matches(), 2);
+        assert_eq!(search.selection(), Some("seerch".to_string()));
+    }
+
+    #[test]
+    fn equally_good_matches_keep_their_input_order() {
+        let choices = vec!["b/x".to_string(),
+        "a/x".to_string(),
+        "x".to_string()];
+        let search = Search::blank(&choices, None, 20).append_to_search("x");
+        assert_eq!(search.selection(), Some("x".to_string()));
+
+        let search = search.down();
+        assert_eq!(search.selection(), Some("b/x".to_string()));
+        let search = search.down();
+        assert_eq!(search.selection(), Some("a/x".to_string()));
+    }
+
+    #[test]
+    fn breaks_ties_by_the_configured_keys() {
+        let choices = vec!["ab/x".to_string(),
+        "x/ab".to_string()];
+        let search = Search::blank(&choices, None, 20).append_to_search("x");
+        assert_eq!(search.selection(), Some("ab/x".to_string()));
+
+        let search = search.with_tiebreaks(vec![Tiebreak::Begin]);
+        assert_eq!(search.selection(), Some("x/ab".to_string()));
+    }
+
+    #[test]
+    fn counts_and_ranks_matches_across_every_shard() {
+        let mut choices = input_times(10000);
+        choices.push("ice".to_string());
+        choices.push("ace".to_string());
+        let search = Search::blank(&choices, None, 5).append_to_search("c").append_to_search("e");
+
+        assert_eq!(search.num_matches(), 10002);
+        assert_eq!(search.result.len(), 5);
+        assert_eq!(search.selection(), Some("ice".to_string()));
+        assert_eq!(search.down().selection(), Some("ace".to_string()));
+    }
+
+    #[test]
+    fn filters_choices_that_arrive_later() {
+        let choices = vec!["one".to_string(), "two".to_string()];
+        let arrivals = vec!["three".to_string(), "twenty".to_string()];
+        let search = Search::blank(&choices, None, 20).append_to_search("t").append_to_search("w");
+        assert_eq!(search.num_matches(), 1);
+
+        let search = search.append_choices(&arrivals);
+        assert_eq!(search.num_matches(), 2);
+        let search = search.down();
+        assert_eq!(search.selection(), Some("twenty".to_string()));
+
+        let search = search.backspace();
+        assert_eq!(search.num_matches(), 3);
+        assert_eq!(search.backspace().num_matches(), 4);
+    }
+
+    #[test]
+    fn shows_arrivals_right_away_without_a_query() {
+        let choices = vec![];
+        let arrivals = vec!["one".to_string()];
+        let search = Search::blank(&choices, None, 20).append_choices(&arrivals);
+
+        assert_eq!(search.num_matches(), 1);
+        assert_eq!(search.selection(), Some("one".to_string()));
+    }
+
+    #[test]
+    fn owns_its_choices_so_it_can_move_to_another_thread() {
+        let search = Search::blank(vec!["one".to_string()], None, 20).append_to_search("t");
+
+        let search = thread::spawn(move || search.append_choices(vec!["two", "three"])).join().unwrap();
+        assert_eq!(search.num_matches(), 2);
+        assert_eq!(search.choices().len(), 3);
+        assert_eq!(search.selection(), Some("two".to_string()));
+    }
+
+    #[test]
+    fn counts_matches_that_cannot_make_it_into_the_results() {
+        let mut choices = vec!["ab".to_string(), "a/b".to_string()];
+        choices.extend((1..101).map(|n| format!("a{}b", "x".repeat(n))));
+        let search = Search::blank(&choices, None, 2).append_to_search("a").append_to_search("b");
+
+        assert_eq!(search.num_matches(), 102);
+        assert_eq!(search.matches().iter().map(|matching| matching.original).collect::<Vec<&str>>(), vec!["ab", "a/b"]);
+    }
+
+    fn numbered(n: usize) -> Vec<String> {
+        (0..n).map(|n| format!("choice {}", n)).collect()
+    }
+
+    #[test]
+    fn moves_down_past_the_visible_limit() {
+        let search = Search::blank(numbered(30), None, 10).append_to_search("c");
+        let search = (0..25).fold(search, |search, _| search.down());
+
+        assert_eq!(search.current, 25);
+        assert_eq!(search.selection(), Some("choice 25".to_string()));
+    }
+
+    #[test]
+    fn keeps_results_around_the_selection_on_screen() {
+        let search = Search::blank(numbered(30), None, 10);
+
+        let search = (0..7).fold(search, |search, _| search.down());
+        assert_eq!(search.top, 0);
+        let search = search.down();
+        assert_eq!(search.top, 1);
+        let search = (0..5).fold(search, |search, _| search.up());
+        assert_eq!(search.top, 1);
+        assert_eq!(search.up().top, 0);
+    }
+
+    #[test]
+    fn pages_through_the_matches() {
+        let search = Search::blank(numbered(30), None, 10).append_to_search("c").page_down();
+        assert_eq!((search.current, search.top), (10, 8));
+
+        let search = search.page_down().page_down();
+        assert_eq!((search.current, search.top), (29, 20));
+        assert_eq!(search.matches().len(), 10);
+
+        let search = search.page_up();
+        assert_eq!((search.current, search.top), (19, 12));
+    }
+
+    #[test]
+    fn jumps_to_the_first_and_last_match() {
+        let search = Search::blank(numbered(30), None, 10).append_to_search("c").last();
+        assert_eq!(search.selection(), Some("choice 29".to_string()));
+        assert_eq!(search.top, 20);
+
+        let search = search.first();
+        assert_eq!((search.current, search.top), (0, 0));
+    }
+
+    #[test]
+    fn loops_around_past_the_retained_results() {
+        let search = Search::blank(numbered(30), None, 10).append_to_search("c");
+
+        let search = search.up();
+        assert_eq!(search.selection(), Some("choice 29".to_string()));
+        assert_eq!(search.down().selection(), Some("choice 0".to_string()));
+    }
+
+    #[test]
+    fn ranks_deeper_matches_as_the_selection_reaches_them() {
+        let mut choices = vec!["ab".to_string(), "a/b".to_string()];
+        choices.extend((1..101).map(|n| format!("a{}b", "x".repeat(n))));
+        let search = Search::blank(&choices, None, 2).append_to_search("a").append_to_search("b");
+
+        let search = search.down().down();
+        assert_eq!(search.selection(), Some("axb".to_string()));
+        assert_eq!(search.last().selection(), Some(format!("a{}b", "x".repeat(100))));
+    }
+
+    #[test]
+    fn marks_choices_across_queries() {
+        let choices = vec!["one".to_string(), "two".to_string(), "three".to_string()];
+        let search = Search::blank(&choices, None, 20).with_multi(None);
+
+        let search = search.append_to_search("h").toggle_mark().backspace().append_to_search("w").toggle_mark();
+        assert_eq!(search.marks.len(), 2);
+        assert_eq!(search.selections(), vec!["two", "three"]);
+        assert_eq!(search.with_mark_order(MarkOrder::Marking).selections(), vec!["three", "two"]);
+    }
+
+    #[test]
+    fn selects_the_highlighted_choice_while_nothing_is_marked() {
+        let choices = vec!["one".to_string(), "two".to_string()];
+        let search = Search::blank(&choices, None, 20).with_multi(None).down();
+
+        assert_eq!(search.selections(), vec!["two"]);
+    }
+
+    #[test]
+    fn moves_on_after_marking_without_looping_around() {
+        let choices = vec!["one".to_string(), "two".to_string()];
+        let search = Search::blank(&choices, None, 20).with_multi(None).toggle_mark();
+
+        assert_eq!(search.current, 1);
+        let search = search.toggle_mark();
+        assert_eq!(search.current, 1);
+        assert_eq!(search.toggle_mark_up().selections(), vec!["one"]);
+    }
+
+    #[test]
+    fn marks_every_match_up_to_the_limit() {
+        let choices = vec!["one".to_string(), "two".to_string(), "three".to_string()];
+        let search = Search::blank(&choices, None, 20).with_multi(None).append_to_search("t").mark_all();
+        assert_eq!(search.selections(), vec!["two", "three"]);
+
+        let search = search.unmark_all().with_multi(Some(1)).mark_all();
+        assert_eq!(search.selections(), vec!["two"]);
+    }
+
+    #[test]
+    fn does_not_mark_without_multi() {
+        let choices = vec!["one".to_string(), "two".to_string()];
+        let search = Search::blank(&choices, None, 20).toggle_mark().mark_all();
+
+        assert!(search.marks.is_empty());
+    }
+
+    #[test]
+    fn finds_the_same_matches_after_typing_on_past_a_trailing_dollar() {
+        let choices = vec!["ab$cab".to_string(), "ab$c".to_string(), "zzz".to_string()];
+        for &tolerance in [Tolerance::Never, Tolerance::Fallback].iter() {
+            let fresh = Search::blank(&choices, None, 20).with_tolerance(tolerance).with_query("ab$c");
+            let typed = "ab$c".chars().fold(Search::blank(&choices, None, 20).with_tolerance(tolerance), |search, key| {
+                search.append_to_search(&key.to_string())
+            });
+
+            assert_eq!(fresh.num_matches(), 2);
+            assert_eq!(typed.num_matches(), 2);
+        }
+    }
 }
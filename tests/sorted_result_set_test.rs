@@ -0,0 +1,104 @@
+extern crate icepick;
+
+#[cfg(test)]
+mod tests {
+    use icepick::score::{Match, Quality};
+    use icepick::sorted_result_set::{SortedResultSet, Rank, Tiebreak};
+
+    fn rank(quality: f32, keys: Vec<usize>, index: usize) -> Rank {
+        Rank { quality: quality, keys: keys, index: index }
+    }
+
+    #[test]
+    fn sorts_by_quality_then_index() {
+        let mut results = SortedResultSet::new(10);
+        results.push("later", rank(1.0, vec![], 3));
+        results.push("worse", rank(0.5, vec![], 0));
+        results.push("earlier", rank(1.0, vec![], 2));
+
+        assert_eq!(results.as_sorted_vec(), vec!["earlier", "later", "worse"]);
+    }
+
+    #[test]
+    fn smaller_tiebreak_keys_rank_higher() {
+        let mut results = SortedResultSet::new(10);
+        results.push("long", rank(1.0, vec![9, 0], 0));
+        results.push("short", rank(1.0, vec![2, 7], 1));
+        results.push("short and early", rank(1.0, vec![2, 1], 2));
+
+        assert_eq!(results.as_sorted_vec(), vec!["short and early", "short", "long"]);
+    }
+
+    #[test]
+    fn keeps_the_best_results_when_full() {
+        let mut results = SortedResultSet::new(2);
+        results.push("first", rank(1.0, vec![], 0));
+        results.push("second", rank(1.0, vec![], 1));
+        results.push("third", rank(1.0, vec![], 2));
+        results.push("best", rank(2.0, vec![], 3));
+
+        assert_eq!(results.as_sorted_vec(), vec!["best", "first"]);
+    }
+
+    #[test]
+    fn computes_tiebreak_keys_from_the_match() {
+        let choice = "src/main.rs".to_string();
+        let matching = Match::new(Quality(1.0), vec![4, 5], &choice);
+        let tiebreaks = [Tiebreak::Length, Tiebreak::Begin, Tiebreak::End, Tiebreak::Index];
+
+        assert_eq!(Rank::new(&matching, 7, &tiebreaks).keys, vec![11, 4, 5, 7]);
+    }
+
+    #[test]
+    fn parses_a_list_of_tiebreaks() {
+        assert_eq!(Tiebreak::parse_list("length,end"), Ok(vec![Tiebreak::Length, Tiebreak::End]));
+        assert!(Tiebreak::parse_list("length,size").is_err());
+    }
+
+    #[test]
+    fn merges_the_best_of_several_sets() {
+        let mut left = SortedResultSet::new(2);
+        left.push("a", rank(1.0, vec![], 0));
+        left.push("b", rank(3.0, vec![], 1));
+        let mut right = SortedResultSet::new(2);
+        right.push("c", rank(2.0, vec![], 2));
+        right.push("d", rank(0.5, vec![], 3));
+        let mut empty = SortedResultSet::new(2);
+        empty.push("e", rank(0.1, vec![], 4));
+
+        let merged = SortedResultSet::merge_all(2, vec![empty, left, right]);
+        assert_eq!(merged.as_sorted_vec(), vec!["b", "c"]);
+    }
+
+    #[test]
+    fn peeks_at_the_worst_retained_rank() {
+        let mut results = SortedResultSet::new(2);
+        assert_eq!(results.worst(), None);
+
+        results.push("a", rank(1.0, vec![], 0));
+        results.push("b", rank(3.0, vec![], 1));
+        results.push("c", rank(2.0, vec![], 2));
+        assert!(results.is_full());
+        assert_eq!(results.worst(), Some(&rank(2.0, vec![], 2)));
+    }
+
+    #[test]
+    fn iterates_best_first_without_consuming() {
+        let mut results = SortedResultSet::new(3);
+        results.push("a", rank(1.0, vec![], 0));
+        results.push("b", rank(3.0, vec![], 1));
+        results.push("c", rank(2.0, vec![], 2));
+
+        let choices: Vec<&str> = results.iter().map(|result| result.choice).collect();
+        assert_eq!(choices, vec!["b", "c", "a"]);
+        assert_eq!(results.len(), 3);
+    }
+
+    #[test]
+    fn keeps_nothing_without_room() {
+        let mut results = SortedResultSet::new(0);
+        results.push("a", rank(1.0, vec![], 0));
+
+        assert!(results.is_empty());
+    }
+}
@@ -0,0 +1,74 @@
+extern crate icepick;
+
+#[cfg(test)]
+mod tests {
+    use icepick::fake_tty::FakeIO;
+    use icepick::ansi::Ansi;
+    use icepick::text::{Text, Printable, Window};
+    use icepick::score::{Match, Quality};
+
+    const WIDE: Window = Window { start: 0, width: 80 };
+
+    pub fn printed(text: Text) -> String {
+        let mut ansi = Ansi { io: Box::new(FakeIO::new()) };
+        text.print(&mut ansi);
+        ansi.io.last().to_string()
+    }
+
+    #[test]
+    fn colors_only_the_matched_characters() {
+        let choice = "a/b/c".to_string();
+        let matching = Match::new(Quality(1.0), vec![0, 4], &choice);
+
+        assert_eq!(printed(Text::Colored(matching, WIDE)), "\x1b[34ma\x1b[0m/b/\x1b[34mc\x1b[0m");
+    }
+
+    #[test]
+    fn groups_consecutive_matched_characters() {
+        let choice = "abcd".to_string();
+        let matching = Match::new(Quality(1.0), vec![1, 2], &choice);
+
+        assert_eq!(printed(Text::Colored(matching, WIDE)), "a\x1b[34mbc\x1b[0md");
+    }
+
+    #[test]
+    fn keeps_the_highlight_inverted_around_matches() {
+        let choice = "abc".to_string();
+        let matching = Match::new(Quality(1.0), vec![1], &choice);
+
+        assert_eq!(printed(Text::Highlight(matching, WIDE)), "\x1b[7ma\x1b[34mb\x1b[0m\x1b[7mc\x1b[0m");
+    }
+
+    #[test]
+    fn colors_non_ascii_characters() {
+        let choice = "añb".to_string();
+        let matching = Match::new(Quality(1.0), vec![1], &choice);
+
+        assert_eq!(printed(Text::Colored(matching, WIDE)), "a\x1b[34mñ\x1b[0mb");
+    }
+
+    #[test]
+    fn marks_text_clipped_on_the_right() {
+        let choice = "src/screen.rs".to_string();
+        let matching = Match::new(Quality(1.0), vec![], &choice);
+
+        assert_eq!(printed(Text::Colored(matching, Window { start: 0, width: 8 })), "src/sc..");
+    }
+
+    #[test]
+    fn marks_text_clipped_on_both_sides() {
+        let choice = "src/screen.rs".to_string();
+        let matching = Match::new(Quality(1.0), vec![4], &choice);
+
+        assert_eq!(printed(Text::Colored(matching, Window { start: 4, width: 8 })),
+                   "..\x1b[34ms\x1b[0mcre..");
+    }
+
+    #[test]
+    fn only_marks_the_left_side_when_the_end_is_visible() {
+        let choice = "src/screen.rs".to_string();
+        let matching = Match::new(Quality(1.0), vec![], &choice);
+
+        assert_eq!(printed(Text::Colored(matching, Window { start: 6, width: 9 })), "..reen.rs");
+    }
+}
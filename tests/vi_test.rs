@@ -0,0 +1,165 @@
+extern crate icepick;
+
+#[cfg(test)]
+mod tests {
+    use icepick::search::Search;
+    use icepick::vi::{self, Mode, Parse, Command, Action, Operator, Target, Motion};
+
+    fn normal(query: &str) -> Search {
+        let choices = vec!["one".to_string(), "two".to_string(), "three".to_string()];
+        let search = Search::blank(&choices, None, 10).with_vi().append_to_search(query);
+        vi::escape(search)
+    }
+
+    fn type_keys(search: Search, keys: &str) -> Search {
+        keys.chars().fold(search, |search, key| vi::normal(search, &key.to_string()))
+    }
+
+    fn parse(keys: &str) -> Parse {
+        vi::parse(&keys.chars().collect::<Vec<char>>())
+    }
+
+    fn mode(search: &Search) -> Mode {
+        search.vi.as_ref().unwrap().mode
+    }
+
+    #[test]
+    fn parses_counts_operators_and_targets() {
+        assert_eq!(parse("2d3w"), Parse::Complete(Command { count: 6, action: Action::Operate(Operator::Delete, Target::Motion(Motion::WordForward(false))) }));
+        assert_eq!(parse("ciW"), Parse::Complete(Command { count: 1, action: Action::Operate(Operator::Change, Target::Object(true, true)) }));
+        assert_eq!(parse("yy"), Parse::Complete(Command { count: 1, action: Action::Operate(Operator::Yank, Target::Line) }));
+        assert_eq!(parse("0"), Parse::Complete(Command { count: 1, action: Action::Move(Motion::Start) }));
+    }
+
+    #[test]
+    fn waits_for_the_rest_of_a_command() {
+        assert_eq!(parse("2"), Parse::Incomplete);
+        assert_eq!(parse("d"), Parse::Incomplete);
+        assert_eq!(parse("ci"), Parse::Incomplete);
+        assert_eq!(parse("dq"), Parse::Invalid);
+        assert_eq!(parse("cix"), Parse::Invalid);
+    }
+
+    #[test]
+    fn switches_to_normal_mode_on_the_last_character() {
+        let search = normal("one");
+
+        assert_eq!(mode(&search), Mode::Normal);
+        assert_eq!(search.prompt.cursor(), 2);
+    }
+
+    #[test]
+    fn moves_by_words() {
+        let search = type_keys(normal("src/screen.rs test"), "0w");
+        assert_eq!(search.prompt.cursor(), 3);
+
+        let search = type_keys(search, "W");
+        assert_eq!(search.prompt.cursor(), 14);
+        let search = type_keys(search, "2b");
+        assert_eq!(search.prompt.cursor(), 10);
+        let search = type_keys(search, "e");
+        assert_eq!(search.prompt.cursor(), 12);
+        assert_eq!(type_keys(search, "$b").prompt.cursor(), 14);
+    }
+
+    #[test]
+    fn stays_on_the_last_character() {
+        let search = type_keys(normal("one"), "0$5l");
+
+        assert_eq!(search.prompt.cursor(), 2);
+    }
+
+    #[test]
+    fn deletes_words() {
+        let search = type_keys(normal("one two three"), "0dw");
+        assert_eq!(search.query, "two three");
+
+        let search = type_keys(normal("one two three"), "02dw");
+        assert_eq!(search.query, "three");
+        assert_eq!(search.prompt.cursor(), 0);
+    }
+
+    #[test]
+    fn changes_a_word_up_to_its_end() {
+        let search = type_keys(normal("one two"), "0cw");
+
+        assert_eq!(search.query, " two");
+        assert_eq!(mode(&search), Mode::Insert);
+    }
+
+    #[test]
+    fn changes_only_to_the_end_of_the_word_from_its_last_character() {
+        assert_eq!(type_keys(normal("foo bar"), "0llcw").query, "fo bar");
+        assert_eq!(type_keys(normal("foo bar baz"), "0ll2cw").query, "fo baz");
+    }
+
+    #[test]
+    fn changes_the_word_under_the_cursor() {
+        let search = type_keys(normal("one two three"), "0wlciw");
+        assert_eq!(search.query, "one  three");
+        assert_eq!(search.prompt.cursor(), 4);
+
+        let search = type_keys(normal("one two three"), "0wdaw");
+        assert_eq!(search.query, "one three");
+        let search = type_keys(normal("one two three"), "daw");
+        assert_eq!(search.query, "one two");
+    }
+
+    #[test]
+    fn deletes_characters_and_to_the_end() {
+        let search = type_keys(normal("three"), "0x");
+        assert_eq!(search.query, "hree");
+
+        let search = type_keys(search, "lD");
+        assert_eq!(search.query, "h");
+        assert_eq!(type_keys(normal("three"), "dd").query, "");
+    }
+
+    #[test]
+    fn puts_what_was_deleted_or_yanked() {
+        let search = type_keys(normal("one two"), "0dwP");
+        assert_eq!(search.query, "one two");
+
+        let search = type_keys(normal("ab"), "0ylp");
+        assert_eq!(search.query, "aab");
+        assert_eq!(search.prompt.cursor(), 1);
+    }
+
+    #[test]
+    fn enters_insert_mode_where_asked() {
+        let search = type_keys(normal("one"), "0a");
+        assert_eq!((mode(&search), search.prompt.cursor()), (Mode::Insert, 1));
+
+        let search = type_keys(vi::escape(search), "A");
+        assert_eq!(search.prompt.cursor(), 3);
+    }
+
+    #[test]
+    fn moves_the_selection_with_j_and_k() {
+        let search = type_keys(normal(""), "2j");
+        assert_eq!(search.selection(), Some("three".to_string()));
+
+        assert_eq!(type_keys(search, "k").selection(), Some("two".to_string()));
+    }
+
+    #[test]
+    fn caps_counts_instead_of_overflowing_or_repeating_them_for_ages() {
+        assert_eq!(parse("99999999999d99999999999w"), Parse::Complete(Command { count: 9999, action: Action::Operate(Operator::Delete, Target::Motion(Motion::WordForward(false))) }));
+
+        let search = type_keys(normal("one two three"), "099999999999d99999999999w");
+        assert_eq!(search.query, "");
+
+        let search = type_keys(normal(""), "9999999999j");
+        assert_eq!(search.selection(), Some("one".to_string()));
+        assert_eq!(type_keys(search, "4k").selection(), Some("three".to_string()));
+    }
+
+    #[test]
+    fn forgets_an_unfinished_command_on_escape() {
+        let search = type_keys(normal("one two"), "0d");
+        let search = type_keys(vi::escape(search), "w");
+
+        assert_eq!(search.query, "one two");
+        assert_eq!(search.prompt.cursor(), 4);
+    }
+}
@@ -0,0 +1,51 @@
+extern crate icepick;
+
+#[cfg(test)]
+mod tests {
+    use icepick::width::{display_width, truncate};
+
+    #[test]
+    fn measures_ascii_by_characters() {
+        assert_eq!(display_width("src/screen.rs"), 13);
+    }
+
+    #[test]
+    fn measures_wide_characters_as_two_columns() {
+        assert_eq!(display_width("日本語.txt"), 10);
+        assert_eq!(display_width("🎉 party"), 8);
+    }
+
+    #[test]
+    fn combining_marks_take_no_space() {
+        assert_eq!(display_width("cafe\u{301}"), 4);
+    }
+
+    #[test]
+    fn escape_sequences_take_no_space() {
+        assert_eq!(display_width("\x1b[34mab\x1b[0mc"), 3);
+        assert_eq!(display_width("\x1b[7m"), 0);
+    }
+
+    #[test]
+    fn tabs_advance_to_the_next_tab_stop() {
+        assert_eq!(display_width("ab\tc"), 9);
+        assert_eq!(truncate("ab\tc", 20), "ab      c");
+    }
+
+    #[test]
+    fn truncates_to_the_given_columns() {
+        assert_eq!(truncate("src/screen.rs", 3), "src");
+        assert_eq!(truncate("ab", 3), "ab");
+    }
+
+    #[test]
+    fn never_splits_a_wide_character() {
+        assert_eq!(truncate("日本語", 5), "日本");
+        assert_eq!(truncate("aé日", 3), "aé");
+    }
+
+    #[test]
+    fn keeps_escape_sequences_after_the_cut() {
+        assert_eq!(truncate("\x1b[7mabc\x1b[0m", 2), "\x1b[7mab\x1b[0m");
+    }
+}